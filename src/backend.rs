@@ -1,4 +1,12 @@
 pub mod backend_manager;
+pub mod create_table;
 pub mod csv_handler;
 pub mod database_handler;
+pub mod db_connection;
+pub mod diff;
+pub mod journal;
 pub mod parser;
+pub mod profiles;
+pub mod query_history;
+pub mod sql_value;
+pub mod storage;
@@ -1,39 +1,72 @@
-use super::csv_handler::{DataEntry, ImportedData};
-use super::database_handler::{DBLoginData, QueryResult, Table, Tables};
-use super::parser::parse;
+use super::create_table::{add_columns_statements, column_names, create_table_statement};
+use super::csv_handler::{CsvDialect, DataEntry, ImportedData};
+use super::database_handler::{
+    dependency_order, plan_insert_batches, ConsoleOutput, DBLoginData, EntriesFilter, ErrorPolicy,
+    ForeignKey, InsertOptions, QueryResult, RowIssue, ScriptKind, ScriptOptions, Table, TableField,
+    Tables,
+};
+use super::db_connection::pool::DBPool;
+use super::db_connection::script::ScriptConnection;
+use super::db_connection::{DBConnection, TextResultSet};
+use super::diff::{diff_against_table, normalized_key, TableDiff};
+use super::journal::{ImportJournal, ImportRecord};
+use super::parser::{check_foreign_key, parse};
+use super::profiles::MasterKey;
+use chrono::Local;
+use futures_util::future::join_all;
 use if_chain::if_chain;
-use sqlx::MySqlConnection;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::sync::{oneshot, Mutex, MutexGuard};
+use tokio::sync::{oneshot, watch, Mutex, MutexGuard};
 pub struct BackendManger {
     pub db_login_data: DBLoginData,
     pub imported_data: ImportedData,
     pub csv_data: Arc<Mutex<ImportedData>>,
-    pub db_connection: Option<MySqlConnection>,
+    pub db_pool: Option<Arc<dyn DBPool>>,
     pub receiver: Receiver<Communication>,
     pub db_table_data: Arc<Mutex<Tables>>,
+    /* values of referenced keys, loaded once per foreign key */
+    pub referenced_values: HashMap<ForeignKey, HashSet<String>>,
+    pub open_transaction: Arc<Mutex<Option<OpenTransaction>>>,
 }
 
 impl BackendManger {
     pub async fn listen(&mut self) {
         if let Some(command) = self.receiver.recv().await {
             match command {
+                Communication::DeriveMasterKey(passphrase, salt, sender) => {
+                    /* the kdf is slow on purpose, keep it off both the ui and this loop */
+                    tokio::task::spawn_blocking(move || {
+                        sender
+                            .send(MasterKey::derive(&passphrase, &salt))
+                            .unwrap_or_else(|_| println!("Failed to respond to DeriveMasterKey"));
+                    });
+                }
                 Communication::ValidateCreditentials(db_login_data, sender) => {
                     self.db_login_data = db_login_data;
 
                     let result = self.db_login_data.validate_creditentials().await;
                     match result {
-                        Ok(connection) => {
-                            self.db_connection = Some(connection);
+                        Ok(pool) => {
+                            self.db_pool = Some(pool);
+                            self.referenced_values.clear();
                             sender.send(Ok(())).unwrap_or_else(|_e| {
                                 println!("failed to send ValidateCreditentials err")
                             });
-                            let mut db_table_data = self.db_table_data.lock().await;
-                            db_table_data
-                                .query_for_tables(&mut self.db_connection.as_mut().unwrap())
-                                .await
-                                .unwrap();
+                            match self.acquire_connection().await {
+                                Ok(mut connection) => {
+                                    let mut db_table_data = self.db_table_data.lock().await;
+                                    db_table_data
+                                        .query_for_tables(connection.as_mut())
+                                        .await
+                                        .unwrap();
+                                }
+                                Err(e) => println!("Failed to query for tables, {}", e),
+                            }
                         }
                         Err(e) => {
                             sender.send(Err(e)).unwrap_or_else(|_e| {
@@ -58,32 +91,192 @@ impl BackendManger {
                     }
                     let mut db_table_data = self.db_table_data.lock().await;
                     let mut csv_data = self.csv_data.lock().await;
-                    try_match_headers_to_fields(&mut db_table_data, None, &mut csv_data)
+                    try_match_headers_to_fields(&mut db_table_data, None, &mut csv_data);
+                    drop((db_table_data, csv_data));
+                    self.check_foreign_keys(None).await;
                 }
 
-                Communication::ImportDBEntries => {
+                Communication::ImportDBEntries(filter) => {
+                    let mut connection = match self.acquire_connection().await {
+                        Ok(connection) => connection,
+                        Err(e) => return println!("Failed to import table entries, {}", e),
+                    };
                     let mut db_table_data = self.db_table_data.lock().await;
-                    let mut csv_data = self.csv_data.lock().await;
 
-                    if let Some(current_working_table_i) = db_table_data.current_working_table {
-                        if let Some(current_working_table) =
-                            db_table_data.tables.get_mut(current_working_table_i)
+                    if let Some(table_index) = db_table_data.current_working_table {
+                        let table = db_table_data.tables.get_mut(table_index).unwrap();
+                        if table.fields.is_none() {
+                            table.describe_table(connection.as_mut()).await;
+                        }
+                        match table
+                            .query_for_table_entries(connection.as_mut(), &filter)
+                            .await
                         {
-                            todo!();
+                            Ok(result_set) => {
+                                println!(
+                                    "Imported {} rows from \"{}\"",
+                                    result_set.rows.len(),
+                                    table.name
+                                );
+                                /* mappings to the previous file's cols don't apply anymore */
+                                for field in table.fields.iter_mut().flatten() {
+                                    field.mapped_to_col = None;
+                                }
+                                let mut csv_data = self.csv_data.lock().await;
+                                csv_data.load_result_set(result_set);
+                                try_match_headers_to_fields(
+                                    &mut db_table_data,
+                                    Some(table_index),
+                                    &mut csv_data,
+                                );
+                            }
+                            Err(e) => println!("Failed to import table entries, {}", e),
+                        }
+                    }
+                    drop(db_table_data);
+                    self.check_foreign_keys(None).await;
+                }
+                Communication::SaveCSV(path, dialect) => {
+                    let mut csv_data = self.csv_data.lock().await;
+                    match csv_data.save_csv(&path, &dialect) {
+                        Ok(_) => {
+                            println!("Saved csv to \"{}\"", path);
+                            csv_data.path = path;
+                        }
+                        Err(e) => println!("Failed to save csv to \"{}\", {}", path, e),
+                    }
+                }
+                Communication::ExportSQLScript(path, script_options) => {
+                    match self.export_script(&path, &script_options).await {
+                        Ok(statement_count) => {
+                            println!("Exported {} statements to \"{}\"", statement_count, path)
+                        }
+                        Err(e) => println!("Failed to export the script to \"{}\", {}", path, e),
+                    }
+                }
+                Communication::ProposeTable(table_name, sender) => {
+                    let csv_data = self.csv_data.lock().await;
+                    let statement =
+                        create_table_statement(&table_name, &csv_data, self.db_login_data.driver);
+                    sender
+                        .send(statement)
+                        .unwrap_or_else(|_| println!("Failed to respond to ProposeTable"));
+                }
+                Communication::CreateTable(statement, sender) => {
+                    let mut connection = match self.acquire_connection().await {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            return sender
+                                .send(connection_error(e))
+                                .unwrap_or_else(|_| println!("Failed to respond to CreateTable"))
                         }
+                    };
+                    let res = match connection.execute(&statement).await {
+                        Ok(res) => QueryResult {
+                            query: statement,
+                            result: Ok(res),
+                        },
+                        Err(e) => QueryResult {
+                            query: statement,
+                            result: Err(Box::new(e)),
+                        },
+                    };
+                    if res.result.is_ok() {
+                        self.select_created_table(connection.as_mut()).await;
                     }
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to CreateTable"));
                 }
-                Communication::SaveCSV(path) => {
-                    todo!()
+                Communication::ProposeColumns(cols, sender) => {
+                    let db_table_data = self.db_table_data.lock().await;
+                    let working_table = db_table_data
+                        .current_working_table
+                        .and_then(|table_index| db_table_data.tables.get(table_index));
+                    let statements = match working_table {
+                        Some(table) => {
+                            let csv_data = self.csv_data.lock().await;
+                            Ok(add_columns_statements(
+                                &table.name,
+                                &csv_data,
+                                &cols,
+                                self.db_login_data.driver,
+                            ))
+                        }
+                        None => Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                            "No table selected to add fields to",
+                        ) as Box<dyn Error + Send>),
+                    };
+                    sender
+                        .send(statements)
+                        .unwrap_or_else(|_| println!("Failed to respond to ProposeColumns"));
+                }
+                Communication::AddColumns(statements, cols, sender) => {
+                    let mut connection = match self.acquire_connection().await {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            return sender
+                                .send(vec![connection_error(e)])
+                                .unwrap_or_else(|_| println!("Failed to respond to AddColumns"))
+                        }
+                    };
+                    /* mysql commits every ALTER on its own, so stopping is all a failure can do */
+                    let mut results: Vec<QueryResult> = Vec::new();
+                    for statement in statements {
+                        let res = match connection.execute(&statement).await {
+                            Ok(res) => QueryResult {
+                                query: statement,
+                                result: Ok(res),
+                            },
+                            Err(e) => QueryResult {
+                                query: statement,
+                                result: Err(Box::new(e)),
+                            },
+                        };
+                        let is_failed = res.result.is_err();
+                        results.push(res);
+                        if is_failed {
+                            break;
+                        }
+                    }
+                    self.redescribe_working_table(connection.as_mut(), &cols)
+                        .await;
+                    sender
+                        .send(results)
+                        .unwrap_or_else(|_| println!("Failed to respond to AddColumns"));
+                }
+                Communication::RunQuery(query, in_open_transaction, sender) => {
+                    let output = self.run_console_query(&query, in_open_transaction).await;
+                    sender
+                        .send(output)
+                        .unwrap_or_else(|_| println!("Failed to respond to RunQuery"));
+                }
+                Communication::LoadResultSet(result_set) => {
+                    let mut db_table_data = self.db_table_data.lock().await;
+                    /* mappings to the previous grid's cols don't apply anymore */
+                    for table in db_table_data.tables.iter_mut() {
+                        for field in table.fields.iter_mut().flatten() {
+                            field.mapped_to_col = None;
+                        }
+                    }
+                    let mut csv_data = self.csv_data.lock().await;
+                    csv_data.load_result_set(result_set);
+                    try_match_headers_to_fields(&mut db_table_data, None, &mut csv_data);
+                    drop((db_table_data, csv_data));
+                    self.check_foreign_keys(None).await;
                 }
                 Communication::GetTableDescription(table_index) => {
+                    let mut connection = match self.acquire_connection().await {
+                        Ok(connection) => connection,
+                        Err(e) => return println!("Failed to describe table, {}", e),
+                    };
                     let mut db_table_data = self.db_table_data.lock().await;
                     let mut csv_data = self.csv_data.lock().await;
                     db_table_data
                         .tables
                         .get_mut(table_index)
                         .unwrap()
-                        .describe_table(&mut self.db_connection.as_mut().unwrap())
+                        .describe_table(connection.as_mut())
                         .await;
 
                     try_match_headers_to_fields(
@@ -91,6 +284,18 @@ impl BackendManger {
                         Some(table_index),
                         &mut csv_data,
                     );
+                    drop((db_table_data, csv_data));
+                    self.check_foreign_keys(None).await;
+                }
+                Communication::KeepOnlyRows(rows) => {
+                    let mut csv_data = self.csv_data.lock().await;
+                    let start_i: usize = csv_data.are_headers.into();
+                    /* backwards, so removing doesn't shift the rows still to check */
+                    for i in (start_i..csv_data.data.rows()).rev() {
+                        if !rows.contains(&i) {
+                            csv_data.data.remove_row(i);
+                        }
+                    }
                 }
                 Communication::RemoveRow(i) => {
                     let mut csv_data = self.csv_data.lock().await;
@@ -114,93 +319,1857 @@ impl BackendManger {
                     if is_whole_table_parsed(&csv_data) {
                         csv_data.is_parsed = true;
                     }
+                    drop(csv_data);
+                    self.check_foreign_keys(Some(col_index)).await;
                 }
-                Communication::StartInserting(sender, oneshot_sender) => {
-                    let csv_data = self.csv_data.lock().await;
+                Communication::CheckForeignKeys => {
+                    /* the referenced tables might have changed since they were loaded */
+                    self.referenced_values.clear();
+                    self.check_foreign_keys(None).await;
+                }
+                Communication::CheckUniqueKeys => {
+                    self.check_unique_keys().await;
+                }
+                Communication::StartInserting(
+                    error_policy,
+                    insert_options,
+                    sender,
+                    progress_sender,
+                    oneshot_sender,
+                ) => match self
+                    .import_job(error_policy, insert_options, &[], sender, progress_sender)
+                    .await
+                {
+                    Some(job) => {
+                        tokio::spawn(job.save(oneshot_sender));
+                    }
+                    None => println!("db - no connection or table to insert into"),
+                },
+                Communication::StartAppending(
+                    error_policy,
+                    insert_options,
+                    workers,
+                    sender,
+                    progress_sender,
+                    oneshot_sender,
+                ) => match self
+                    .import_job(error_policy, insert_options, &[], sender, progress_sender)
+                    .await
+                {
+                    Some(job) if workers > 1 => {
+                        /* sqlite locks the whole file for every write, more workers would only wait on each other */
+                        let workers = if self.db_login_data.driver.is_file_based() {
+                            1
+                        } else {
+                            workers
+                        };
+                        tokio::spawn(job.append_in_parallel(workers, oneshot_sender));
+                    }
+                    Some(job) => {
+                        tokio::spawn(job.append(oneshot_sender));
+                    }
+                    None => println!("db - no connection or table to append to"),
+                },
+                Communication::StartAppendingToTables(
+                    error_policy,
+                    insert_options,
+                    sender,
+                    progress_sender,
+                    oneshot_sender,
+                ) => match self
+                    .import_job(error_policy, insert_options, &[], sender, progress_sender)
+                    .await
+                {
+                    Some(job) => {
+                        tokio::spawn(job.append_to_tables(oneshot_sender));
+                    }
+                    None => println!("db - no connection or table to append to"),
+                },
+                Communication::StartUpserting(
+                    key_fields,
+                    error_policy,
+                    insert_options,
+                    sender,
+                    progress_sender,
+                    oneshot_sender,
+                ) => match self
+                    .import_job(
+                        error_policy,
+                        insert_options,
+                        &key_fields,
+                        sender,
+                        progress_sender,
+                    )
+                    .await
+                {
+                    Some(job) => {
+                        tokio::spawn(job.upsert(key_fields, oneshot_sender));
+                    }
+                    None => println!("db - no connection or table to upsert into"),
+                },
+                Communication::StartTestImport(
+                    insert_options,
+                    sender,
+                    progress_sender,
+                    report_sender,
+                ) => match self
+                    .import_job(
+                        ErrorPolicy::SkipFailedRows,
+                        insert_options,
+                        &[],
+                        sender,
+                        progress_sender,
+                    )
+                    .await
+                {
+                    Some(job) => {
+                        tokio::spawn(job.test(report_sender));
+                    }
+                    None => println!("db - no connection or table to test importing into"),
+                },
+                Communication::MarkRowIssues(issues) => {
+                    let mut csv_data = self.csv_data.lock().await;
+                    let (rows, cols) = (csv_data.data.rows(), csv_data.data.cols());
+                    for issue in issues {
+                        let col = match issue.col {
+                            Some(col) if col < cols && issue.row < rows => col,
+                            _ => continue,
+                        };
+                        let message = format!(
+                            "{}: {}",
+                            if issue.is_error { "Error" } else { "Warning" },
+                            issue.message
+                        );
+                        csv_data.data[issue.row][col].is_parsed =
+                            Some(Err(Arc::from(<String as Into<
+                                Box<dyn std::error::Error + Send + Sync>,
+                            >>::into(message))));
+                        csv_data.parsed_cols.retain(|parsed_col| *parsed_col != col);
+                    }
+                    csv_data.is_parsed = is_whole_table_parsed(&csv_data);
+                }
+                Communication::DiffWithTable(key_field, sender) => {
+                    let diff = self.diff_with_table(&key_field).await;
+                    sender
+                        .send(diff)
+                        .unwrap_or_else(|_| println!("Failed to respond to DiffWithTable"));
+                }
+                Communication::ListSnapshots(sender) => {
                     let db_table_data = self.db_table_data.lock().await;
-                    let table_index = db_table_data.current_working_table.unwrap();
-                    let table = db_table_data.tables.get(table_index).unwrap();
-
-                    let trans_start =
-                        Table::start_transaction(self.db_connection.as_mut().unwrap()).await;
+                    let snapshots = match db_table_data
+                        .current_working_table
+                        .and_then(|table_index| db_table_data.tables.get(table_index))
+                    {
+                        Some(table) => match self.acquire_connection().await {
+                            Ok(mut connection) => {
+                                table.query_for_snapshots(connection.as_mut()).await
+                            }
+                            Err(e) => Err(e),
+                        },
+                        None => Ok(Vec::new()),
+                    };
                     sender
-                        .send(trans_start)
-                        .await
-                        .unwrap_or_else(|_| println!("db- failed to send Transaction Start"));
+                        .send(snapshots)
+                        .unwrap_or_else(|_| println!("Failed to respond to ListSnapshots"));
+                }
+                Communication::RestoreSnapshot(snapshot, sender) => {
+                    let db_table_data = self.db_table_data.lock().await;
+                    let res = match db_table_data
+                        .current_working_table
+                        .and_then(|table_index| db_table_data.tables.get(table_index))
+                    {
+                        Some(table) => match self.acquire_connection().await {
+                            Ok(mut connection) => {
+                                /* so the restore itself can be undone */
+                                let backup = table.create_snapshot(connection.as_mut()).await;
+                                if backup.result.is_err() {
+                                    backup
+                                } else {
+                                    table.restore_snapshot(connection.as_mut(), &snapshot).await
+                                }
+                            }
+                            Err(e) => connection_error(e),
+                        },
+                        None => QueryResult {
+                            query: format!("-- restore {}", snapshot),
+                            result: Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                                "No table selected",
+                            )),
+                        },
+                    };
+                    println!(
+                        "      | Query: {}\n       > Result: {:?}",
+                        res.query, res.result
+                    );
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to RestoreSnapshot"));
+                }
+                Communication::DeleteSnapshot(snapshot, sender) => {
+                    let res = match self.acquire_connection().await {
+                        Ok(mut connection) => {
+                            Table::delete_snapshot(connection.as_mut(), &snapshot).await
+                        }
+                        Err(e) => connection_error(e),
+                    };
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to DeleteSnapshot"));
+                }
+                Communication::ListImports(sender) => {
+                    let database = self.db_login_data.identifier();
+                    /* newest first */
+                    let records: Vec<ImportRecord> = ImportJournal::load()
+                        .records
+                        .into_iter()
+                        .rev()
+                        .filter(|record| record.database == database)
+                        .collect();
+                    sender
+                        .send(records)
+                        .unwrap_or_else(|_| println!("Failed to respond to ListImports"));
+                }
+                Communication::CheckImportUndo(id, sender) => {
+                    let res = match (
+                        ImportJournal::load().get(id),
+                        self.acquire_connection().await,
+                    ) {
+                        (Some(record), Ok(mut connection)) => {
+                            let table = Table {
+                                name: record.table.clone(),
+                                ..Default::default()
+                            };
+                            table
+                                .query_rows_by_keys(
+                                    connection.as_mut(),
+                                    &record.key_field,
+                                    &record.keys(),
+                                )
+                                .await
+                                .map(|current_rows| record.compare_with(&current_rows))
+                        }
+                        (_, Err(e)) => Err(e),
+                        (None, _) => Err(<String as Into<
+                            Box<dyn std::error::Error + Send + Sync>,
+                        >>::into(format!(
+                            "No import {} in the journal",
+                            id
+                        ))
+                            as Box<dyn std::error::Error + Send>),
+                    };
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to CheckImportUndo"));
+                }
+                Communication::UndoImport(id, sender) => {
+                    let journal = ImportJournal::load();
+                    let res = match (journal.get(id), self.acquire_connection().await) {
+                        (Some(record), Ok(mut connection)) => {
+                            let table = Table {
+                                name: record.table.clone(),
+                                ..Default::default()
+                            };
+                            table
+                                .delete_rows_by_keys(
+                                    connection.as_mut(),
+                                    &record.key_field,
+                                    &record.keys(),
+                                )
+                                .await
+                        }
+                        (_, Err(e)) => connection_error(e),
+                        (None, _) => QueryResult {
+                            query: "-- undo import".to_owned(),
+                            result: Err(<String as Into<
+                                Box<dyn std::error::Error + Send + Sync>,
+                            >>::into(format!(
+                                "No import {} in the journal",
+                                id
+                            ))),
+                        },
+                    };
+                    println!(
+                        "      | Query: {}\n       > Result: {:?}",
+                        res.query, res.result
+                    );
+                    if res.result.is_ok() {
+                        ImportJournal::mark_undone(id).unwrap_or_else(|e| {
+                            println!("Failed to save the import journal, {}", e)
+                        });
+                    }
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to UndoImport"));
+                }
+                Communication::TryCommit(sender) => {
+                    let open_transaction = self.open_transaction.lock().await.take();
+                    let res = match open_transaction {
+                        Some(mut open_transaction) => {
+                            let res =
+                                Table::transaction_commit(open_transaction.connection.as_mut())
+                                    .await;
+                            if res.result.is_ok() {
+                                for record in open_transaction.pending_imports {
+                                    ImportJournal::add(record).unwrap_or_else(|e| {
+                                        println!("Failed to save the import journal, {}", e)
+                                    });
+                                }
+                            } else {
+                                /* still open, it can be tried again or rolled back */
+                                *self.open_transaction.lock().await = Some(open_transaction);
+                            }
+                            res
+                        }
+                        None => no_open_transaction("COMMIT"),
+                    };
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to TryCommit"));
+                }
+                Communication::TryRollBack(sender) => {
+                    let open_transaction = self.open_transaction.lock().await.take();
+                    let res = match open_transaction {
+                        Some(mut open_transaction) => {
+                            Table::transaction_rollback(open_transaction.connection.as_mut()).await
+                        }
+                        None => no_open_transaction("ROLLBACK"),
+                    };
+                    sender
+                        .send(res)
+                        .unwrap_or_else(|_| println!("Failed to respond to TryRollBack"));
+                }
+            }
+        }
+    }
+}
+impl BackendManger {
+    /* Every command takes its own connection from the pool, so none has to wait for a running insert */
+    async fn acquire_connection(&self) -> Result<Box<dyn DBConnection>, Box<dyn Error + Send>> {
+        let pool = match &self.db_pool {
+            Some(pool) => pool,
+            None => {
+                return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "Not connected to a database",
+                ))
+            }
+        };
+        match pool.acquire().await {
+            Ok(connection) => Ok(connection),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+    /* Copies what an insert needs, with `insert_options` applied to the copies.
+     * None without a connection or a table to insert into */
+    async fn import_job(
+        &self,
+        error_policy: ErrorPolicy,
+        insert_options: InsertOptions,
+        key_fields: &[String],
+        sender: Sender<QueryResult>,
+        progress_sender: watch::Sender<(usize, usize)>,
+    ) -> Option<ImportJob> {
+        let mut tables: Vec<Table> = {
+            let db_table_data = self.db_table_data.lock().await;
+            db_table_data
+                .import_tables()
+                .into_iter()
+                .filter_map(|table_index| db_table_data.tables.get(table_index).cloned())
+                .collect()
+        };
+        let mut csv_data = self.csv_data.lock().await.clone();
+        insert_options.apply(&mut tables, &mut csv_data, key_fields);
+        let mut tables = tables.into_iter();
+        Some(ImportJob {
+            pool: self.db_pool.clone()?,
+            table: tables.next()?,
+            linked_tables: tables.collect(),
+            csv_data,
+            database: self.db_login_data.identifier(),
+            error_policy,
+            sender,
+            progress_sender,
+            open_transaction: self.open_transaction.clone(),
+        })
+    }
+    /* Writes the statements importing the grid would run into the .sql file at `path`.
+     * Only the loaded table descriptions are needed, so it works for databases that
+     * can't be reached from here. Returns how many statements were written */
+    async fn export_script(
+        &self,
+        path: &str,
+        options: &ScriptOptions,
+    ) -> Result<usize, Box<dyn Error + Send>> {
+        let mut tables: Vec<Table> = {
+            let db_table_data = self.db_table_data.lock().await;
+            db_table_data
+                .import_tables()
+                .into_iter()
+                .filter_map(|table_index| db_table_data.tables.get(table_index).cloned())
+                .collect()
+        };
+        /* the children's foreign keys are only known once the parents are inserted */
+        if tables.len() > 1 {
+            return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                "Rows going into linked tables can't be exported as a script",
+            ));
+        }
+        let mut csv_data = self.csv_data.lock().await.clone();
+        let key_fields = match options.kind {
+            ScriptKind::Upsert => options.key_fields.clone(),
+            ScriptKind::Append | ScriptKind::Replace => Vec::new(),
+        };
+        options
+            .insert_options
+            .apply(&mut tables, &mut csv_data, &key_fields);
+        let table = match tables.first() {
+            Some(table) => table,
+            None => {
+                return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "No table selected to export for",
+                ))
+            }
+        };
+        let mut script = ScriptConnection::new(self.db_login_data.driver);
+        write_script(&mut script, table, &csv_data, options).await?;
+        match std::fs::write(path, script.script()) {
+            Ok(_) => Ok(script.statement_count()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+    /* Makes the table CreateTable just made the working table, with every col
+     * mapped to the field that was proposed for it */
+    async fn select_created_table(&mut self, connection: &mut dyn DBConnection) {
+        let mut db_table_data = self.db_table_data.lock().await;
+        let new_tables = match db_table_data.query_for_new_tables(connection).await {
+            Ok(new_tables) => new_tables,
+            Err(e) => return println!("Failed to query for tables, {}", e),
+        };
+        /* anything else would be a table someone else created meanwhile */
+        let table_index = match new_tables.as_slice() {
+            [table_index] => *table_index,
+            _ => return println!("Couldn't tell which of the new tables was created"),
+        };
+        db_table_data.current_working_table = Some(table_index);
+        db_table_data.linked_tables.clear();
+        let table = db_table_data.tables.get_mut(table_index).unwrap();
+        table.describe_table(connection).await;
 
-                    let start_i: usize = csv_data.are_headers.into();
+        let mut csv_data = self.csv_data.lock().await;
+        /* what the cols were mapped to before belongs to another table */
+        for cell in csv_data.data.iter_mut() {
+            cell.curr_field_description = None;
+            cell.is_parsed = None;
+        }
+        let names = column_names(&csv_data);
+        for field in table.fields.iter_mut().flatten() {
+            field.mapped_to_col = names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(&field.description.field));
+            if let Some(col_index) = field.mapped_to_col {
+                println!(
+                    "      > automapping field \"{}\" to col \"{}\"",
+                    &field.description.field, col_index
+                );
+                for cell in csv_data.data.iter_col_mut(col_index) {
+                    cell.curr_field_description = Some(field.description.clone());
+                    parse(cell);
+                }
+            }
+        }
+        csv_data.parsed_cols.clear();
+        for col_index in 0..csv_data.data.cols() {
+            if is_whole_col_parsed(&mut csv_data, col_index) {
+                csv_data.parsed_cols.push(col_index);
+            }
+        }
+        csv_data.is_parsed = is_whole_table_parsed(&csv_data);
+    }
+    /* Describes the working table again once fields were added to it. The old mappings stay,
+     * new fields named like one of `cols` get mapped to that col */
+    async fn redescribe_working_table(
+        &mut self,
+        connection: &mut dyn DBConnection,
+        cols: &[usize],
+    ) {
+        let mut db_table_data = self.db_table_data.lock().await;
+        let table = match db_table_data
+            .current_working_table
+            .and_then(|table_index| db_table_data.tables.get_mut(table_index))
+        {
+            Some(table) => table,
+            None => return,
+        };
+        let mut mappings: HashMap<String, usize> = table
+            .fields
+            .iter()
+            .flatten()
+            .filter_map(|field| {
+                field
+                    .mapped_to_col
+                    .map(|col_index| (field.description.field.clone(), col_index))
+            })
+            .collect();
+        let mut csv_data = self.csv_data.lock().await;
+        let names = column_names(&csv_data);
+        for col_index in cols {
+            if let Some(name) = names.get(*col_index) {
+                mappings.insert(name.clone(), *col_index);
+            }
+        }
+        table.fields = None;
+        table.describe_table(connection).await;
 
-                    let res = table
-                        .truncate_table(self.db_connection.as_mut().unwrap())
-                        .await;
-                    sender.send(res).await.unwrap_or_else(|_| {
-                        println!("db - failed to send start of insert into table")
-                    });
-                    for i in start_i..csv_data.data.rows() {
-                        let row: Vec<&DataEntry> = csv_data.data[i].iter().collect();
-                        let res = table
-                            .insert_into_table(self.db_connection.as_mut().unwrap(), row)
-                            .await;
+        for field in table.fields.iter_mut().flatten() {
+            field.mapped_to_col = mappings.get(&field.description.field).copied();
+            match field.mapped_to_col {
+                Some(col_index) if cols.contains(&col_index) => {
+                    println!(
+                        "      > automapping field \"{}\" to col \"{}\"",
+                        &field.description.field, col_index
+                    );
+                    for cell in csv_data.data.iter_col_mut(col_index) {
+                        cell.curr_field_description = Some(field.description.clone());
+                        parse(cell);
+                    }
+                    if is_whole_col_parsed(&mut csv_data, col_index) {
+                        csv_data.parsed_cols.push(col_index);
+                    }
+                }
+                _ => {}
+            }
+        }
+        csv_data.is_parsed = is_whole_table_parsed(&csv_data);
+    }
+    /* Compares the grid with the working table's rows that share a key with it.
+     * Only those are fetched, and without holding the grid while the database answers */
+    async fn diff_with_table(&self, key_field: &str) -> Result<TableDiff, Box<dyn Error + Send>> {
+        let (table, keys) = {
+            let csv_data = self.csv_data.lock().await;
+            let db_table_data = self.db_table_data.lock().await;
+            let table = match db_table_data
+                .current_working_table
+                .and_then(|table_index| db_table_data.tables.get(table_index))
+            {
+                Some(table) => table.clone(),
+                None => {
+                    return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                        "No table selected",
+                    ))
+                }
+            };
+            let key_col = table
+                .fields
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .find(|field| field.description.field == key_field)
+                .and_then(|field| field.mapped_to_col);
+            let start_i: usize = csv_data.are_headers.into();
+            let mut keys: Vec<String> = Vec::new();
+            if let Some(key_col) = key_col {
+                let mut seen_keys: HashSet<&str> = HashSet::new();
+                for row in start_i..csv_data.data.rows() {
+                    let key = csv_data.data[row][key_col].data.as_str();
+                    if !key.is_empty() && seen_keys.insert(key) {
+                        keys.push(key.to_owned());
+                    }
+                }
+            }
+            (table, keys)
+        };
+        let mut connection = self.acquire_connection().await?;
+        let mut db_rows = table
+            .query_rows_by_keys(connection.as_mut(), key_field, &keys)
+            .await?;
+        drop(connection);
+        /* nothing to look up still has the table's fields to compare with */
+        if db_rows.columns.is_empty() {
+            db_rows.columns = table
+                .fields
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|field| field.description.field.clone())
+                .collect();
+        }
+        let csv_data = self.csv_data.lock().await;
+        diff_against_table(
+            &csv_data,
+            table.fields.as_deref().unwrap_or_default(),
+            key_field,
+            &db_rows,
+        )
+    }
+    /* Runs SQL typed into the console. Inside the import's open transaction it sees
+     * the rows that aren't committed yet, and anything it changes is committed or rolled back with them */
+    async fn run_console_query(
+        &self,
+        query: &str,
+        in_open_transaction: bool,
+    ) -> Result<ConsoleOutput, Box<dyn Error + Send>> {
+        if in_open_transaction {
+            let mut open_transaction = self.open_transaction.lock().await;
+            return match open_transaction.as_mut() {
+                Some(open_transaction) => {
+                    run_console_query(open_transaction.connection.as_mut(), query).await
+                }
+                None => Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "No open transaction",
+                )),
+            };
+        }
+        let mut connection = self.acquire_connection().await?;
+        run_console_query(connection.as_mut(), query).await
+    }
+    /* Reparses the mapped foreign key cols (or just `col_index`), then marks
+     * every cell whose value is missing from the referenced table */
+    async fn check_foreign_keys(&mut self, col_index: Option<usize>) {
+        let mut connection = match self.acquire_connection().await {
+            Ok(connection) => connection,
+            Err(_) => return,
+        };
+        let db_table_data = self.db_table_data.lock().await;
+        let mut csv_data = self.csv_data.lock().await;
+        let fields = match db_table_data
+            .current_working_table
+            .and_then(|table_index| db_table_data.tables.get(table_index))
+            .and_then(|table| table.fields.as_ref())
+        {
+            Some(fields) => fields,
+            None => return,
+        };
+        for field in fields {
+            let (col, foreign_key) = match (field.mapped_to_col, &field.description.foreign_key) {
+                (Some(col), Some(foreign_key)) if col_index.is_none_or(|i| i == col) => {
+                    (col, foreign_key)
+                }
+                _ => continue,
+            };
+            if col >= csv_data.data.cols() {
+                continue;
+            }
+            if !self.referenced_values.contains_key(foreign_key) {
+                match Table::query_distinct_values(
+                    connection.as_mut(),
+                    &foreign_key.table,
+                    &foreign_key.field,
+                )
+                .await
+                {
+                    Ok(values) => {
                         println!(
-                            "      | Query: {}\n       > Result: {:?}",
-                            res.query, res.result
+                            "Loaded {} values of {}({})",
+                            values.len(),
+                            foreign_key.table,
+                            foreign_key.field
                         );
-                        sender.send(res).await.unwrap_or_else(|_| {
-                            println!("db - failed to send end of insert into table")
-                        });
+                        self.referenced_values.insert(foreign_key.clone(), values);
+                    }
+                    Err(e) => {
+                        println!(
+                            "Failed to load values of {}({}), {}",
+                            foreign_key.table, foreign_key.field, e
+                        );
+                        continue;
                     }
-                    oneshot_sender.send(true).unwrap_or_else(|_| {
-                        println!("db - failed to send end of insert into table")
-                    });
                 }
-                Communication::StartAppending(sender, oneshot_sender) => {
-                    let csv_data = self.csv_data.lock().await;
-                    let db_table_data = self.db_table_data.lock().await;
-                    let table_index = db_table_data.current_working_table.unwrap();
-                    let table = db_table_data.tables.get(table_index).unwrap();
-                    let trans_start =
-                        Table::start_transaction(self.db_connection.as_mut().unwrap()).await;
+            }
+            let referenced_values = &self.referenced_values[foreign_key];
 
-                    sender
-                        .send(trans_start)
-                        .await
-                        .unwrap_or_else(|_| println!("db- failed to send Transaction Start"));
+            let start_i: usize = csv_data.are_headers.into();
+            for row in start_i..csv_data.data.rows() {
+                let cell = &mut csv_data.data[row][col];
+                if cell.curr_field_description.is_some() {
+                    parse(cell);
+                    check_foreign_key(cell, foreign_key, referenced_values);
+                }
+            }
+            if is_whole_col_parsed(&mut csv_data, col) {
+                if !csv_data.parsed_cols.contains(&col) {
+                    csv_data.parsed_cols.push(col);
+                }
+            } else {
+                csv_data.parsed_cols.retain(|parsed_col| *parsed_col != col);
+            }
+            csv_data.is_parsed = is_whole_table_parsed(&csv_data);
+        }
+    }
+    /* Marks cells of every fully mapped UNIQUE/PRIMARY index that repeat
+     * within the grid or already exist in the table */
+    async fn check_unique_keys(&mut self) {
+        let table = {
+            let db_table_data = self.db_table_data.lock().await;
+            match db_table_data
+                .current_working_table
+                .and_then(|table_index| db_table_data.tables.get(table_index))
+            {
+                Some(table) => table.clone(),
+                None => return,
+            }
+        };
+        let (fields, mut connection) = match (&table.fields, self.acquire_connection().await) {
+            (Some(fields), Ok(connection)) => (fields, connection),
+            _ => return,
+        };
+        let key_sets = match connection.unique_key_sets(&table.name).await {
+            Ok(key_sets) => key_sets,
+            Err(e) => {
+                println!("Failed to query unique indexes, {}", e);
+                return;
+            }
+        };
+        /* only indexes whose every field is mapped can collide, eg. an unmapped auto_increment id can't */
+        let key_sets: Vec<Vec<&TableField>> = key_sets
+            .iter()
+            .filter_map(|key_set| {
+                key_set
+                    .iter()
+                    .map(|key_field| {
+                        fields.iter().find(|field| {
+                            &field.description.field == key_field && field.mapped_to_col.is_some()
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
 
-                    let start_i: usize = csv_data.are_headers.into();
+        /* reparse first so conflicts fixed since the last check go away */
+        {
+            let mut csv_data = self.csv_data.lock().await;
+            let start_i: usize = csv_data.are_headers.into();
+            for field in key_sets.iter().flatten() {
+                let col = field.mapped_to_col.unwrap();
+                for row in start_i..csv_data.data.rows() {
+                    let cell = &mut csv_data.data[row][col];
+                    if cell.curr_field_description.is_some() {
+                        parse(cell);
+                    }
+                }
+            }
+        }
+        self.check_foreign_keys(None).await;
 
-                    for i in start_i..csv_data.data.rows() {
-                        let row: Vec<&DataEntry> = csv_data.data[i].iter().collect();
-                        let res = table
-                            .insert_into_table(self.db_connection.as_mut().unwrap(), row)
-                            .await;
-                        println!(
-                            "      | Query: {}\n       > Result: {:?}",
-                            res.query, res.result
-                        );
-                        sender
-                            .send(res)
-                            .await
-                            .unwrap_or_else(|_| println!("db - failed to send append to table"));
+        let mut csv_data = self.csv_data.lock().await;
+        for key_fields in key_sets {
+            let key_names: Vec<&str> = key_fields
+                .iter()
+                .map(|field| field.description.field.as_str())
+                .collect();
+            let existing_keys: HashSet<Vec<String>> = match connection
+                .fetch_rows_as_text(
+                    format!("SELECT {} FROM {}", key_names.join(", "), table.name).as_str(),
+                )
+                .await
+            {
+                /* NULLs never collide */
+                Ok(result_set) => result_set
+                    .rows
+                    .into_iter()
+                    .filter(|row| row.iter().all(|value| value.is_some()))
+                    .map(|row| {
+                        key_fields
+                            .iter()
+                            .zip(row)
+                            .map(|(field, value)| {
+                                normalized_key(&field.description, value.as_deref())
+                            })
+                            .collect()
+                    })
+                    .collect(),
+                Err(e) => {
+                    println!("Failed to query existing keys of \"{}\", {}", table.name, e);
+                    HashSet::new()
+                }
+            };
+            mark_unique_conflicts(&mut csv_data, &key_fields, &existing_keys, &table.name);
+        }
+        for col in 0..csv_data.data.cols() {
+            if is_whole_col_parsed(&mut csv_data, col) {
+                if !csv_data.parsed_cols.contains(&col) {
+                    csv_data.parsed_cols.push(col);
+                }
+            } else {
+                csv_data.parsed_cols.retain(|parsed_col| *parsed_col != col);
+            }
+        }
+        csv_data.is_parsed = is_whole_table_parsed(&csv_data);
+    }
+}
+/* Statements that return rows give their rows, anything else how many rows it changed */
+async fn run_console_query(
+    connection: &mut dyn DBConnection,
+    query: &str,
+) -> Result<ConsoleOutput, Box<dyn Error + Send>> {
+    let keyword = query
+        .trim_start()
+        .trim_start_matches('(')
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_uppercase();
+    let returns_rows = matches!(
+        keyword.as_str(),
+        "SELECT"
+            | "WITH"
+            | "SHOW"
+            | "DESCRIBE"
+            | "DESC"
+            | "EXPLAIN"
+            | "PRAGMA"
+            | "VALUES"
+            | "TABLE"
+    );
+    let output = if returns_rows {
+        connection
+            .fetch_rows_as_text(query)
+            .await
+            .map(ConsoleOutput::Rows)
+    } else {
+        connection
+            .execute(query)
+            .await
+            .map(|res| ConsoleOutput::RowsAffected(res.rows_affected()))
+    };
+    match output {
+        Ok(output) => Ok(output),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+fn connection_error(e: Box<dyn Error + Send>) -> QueryResult {
+    QueryResult {
+        query: "-- connect".to_owned(),
+        result: Err(e),
+    }
+}
+fn no_open_transaction(query: &str) -> QueryResult {
+    QueryResult {
+        query: query.to_owned(),
+        result: Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+            "No open transaction",
+        )),
+    }
+}
+/* A transaction an insert left open, until TryCommit or TryRollBack ends it */
+pub struct OpenTransaction {
+    pub connection: Box<dyn DBConnection>,
+    /* the append's rows, one record per table, journaled once it's committed */
+    pub pending_imports: Vec<ImportRecord>,
+}
+
+/* An insert running on a task of its own, so the backend keeps answering other commands.
+ * It works on a copy of the grid, edits made in the meantime don't change what gets sent */
+pub struct ImportJob {
+    pool: Arc<dyn DBPool>,
+    table: Table,
+    /* only appended to by append_to_tables */
+    linked_tables: Vec<Table>,
+    csv_data: ImportedData,
+    database: String,
+    error_policy: ErrorPolicy,
+    sender: Sender<QueryResult>,
+    progress_sender: watch::Sender<(usize, usize)>,
+    open_transaction: Arc<Mutex<Option<OpenTransaction>>>,
+}
+impl ImportJob {
+    /* Deletes every row and inserts the grid in their place */
+    pub async fn save(self, finished_sender: oneshot::Sender<Vec<usize>>) {
+        /* the old rows are only recoverable from the snapshot once DELETE is committed */
+        let mut connection = match self.begin(true).await {
+            Some(connection) => connection,
+            None => return self.skip_all(finished_sender),
+        };
+        let res = self.table.truncate_table(connection.as_mut()).await;
+        let is_truncate_failed = res.result.is_err();
+        self.log(res).await;
+        /* inserting on top of the old rows would append duplicates instead of replacing them */
+        if is_truncate_failed {
+            let res = Table::transaction_rollback(connection.as_mut()).await;
+            self.log(res).await;
+            return self.skip_all(finished_sender);
+        }
+        let skipped_rows = insert_rows(
+            connection.as_mut(),
+            &self.table,
+            &self.csv_data,
+            None,
+            self.error_policy,
+            &self.sender,
+            &self.progress_sender,
+            &mut None,
+        )
+        .await;
+        self.keep_open(connection, Vec::new(), skipped_rows, finished_sender)
+            .await;
+    }
+    pub async fn append(self, finished_sender: oneshot::Sender<Vec<usize>>) {
+        let mut connection = match self.begin(false).await {
+            Some(connection) => connection,
+            None => return self.skip_all(finished_sender),
+        };
+        let mut inserted_keys = Some(Vec::new());
+        let skipped_rows = insert_rows(
+            connection.as_mut(),
+            &self.table,
+            &self.csv_data,
+            None,
+            self.error_policy,
+            &self.sender,
+            &self.progress_sender,
+            &mut inserted_keys,
+        )
+        .await;
+        /* journaled once the append gets committed */
+        let pending_import = match inserted_keys {
+            Some(keys) if !keys.is_empty() => {
+                record_import(
+                    connection.as_mut(),
+                    &self.table,
+                    self.database.clone(),
+                    keys,
+                )
+                .await
+            }
+            _ => None,
+        };
+        self.keep_open(
+            connection,
+            pending_import.into_iter().collect(),
+            skipped_rows,
+            finished_sender,
+        )
+        .await;
+    }
+    /* Appends every row to the working table and all the linked ones in one transaction,
+     * parents before the tables referencing them */
+    pub async fn append_to_tables(self, finished_sender: oneshot::Sender<Vec<usize>>) {
+        let tables = std::iter::once(self.table.clone())
+            .chain(self.linked_tables.iter().cloned())
+            .collect();
+        let tables = match dependency_order(tables) {
+            Ok(tables) => tables,
+            Err(e) => {
+                self.log(QueryResult {
+                    query: "-- order tables".to_owned(),
+                    result: Err(e),
+                })
+                .await;
+                return self.skip_all(finished_sender);
+            }
+        };
+        println!(
+            "Appending to {}",
+            tables
+                .iter()
+                .map(|table| table.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(" -> ")
+        );
+        let mut connection = match self.begin(false).await {
+            Some(connection) => connection,
+            None => return self.skip_all(finished_sender),
+        };
+        let mut inserted_keys = vec![Some(Vec::new()); tables.len()];
+        let skipped_rows = insert_rows_into_tables(
+            connection.as_mut(),
+            &tables,
+            &self.csv_data,
+            self.error_policy,
+            &self.sender,
+            &self.progress_sender,
+            &mut inserted_keys,
+            &mut None,
+        )
+        .await;
+        /* children come later in the journal, so they're undone before their parents */
+        let mut pending_imports = Vec::new();
+        for (table, keys) in tables.iter().zip(inserted_keys) {
+            if let Some(keys) = keys.filter(|keys| !keys.is_empty()) {
+                if let Some(record) =
+                    record_import(connection.as_mut(), table, self.database.clone(), keys).await
+                {
+                    pending_imports.push(record);
+                }
+            }
+        }
+        self.keep_open(connection, pending_imports, skipped_rows, finished_sender)
+            .await;
+    }
+    /* Appends every row like append_to_tables, collecting what the server says about each,
+     * then rolls it all back. Triggers, CHECK constraints and the sql_mode only act server-side */
+    pub async fn test(
+        self,
+        report_sender: oneshot::Sender<Result<Vec<RowIssue>, Box<dyn Error + Send>>>,
+    ) {
+        let tables = std::iter::once(self.table.clone())
+            .chain(self.linked_tables.iter().cloned())
+            .collect();
+        let report = match dependency_order(tables) {
+            Ok(tables) => match self.begin(false).await {
+                Some(mut connection) => {
+                    let mut issues = Some(Vec::new());
+                    insert_rows_into_tables(
+                        connection.as_mut(),
+                        &tables,
+                        &self.csv_data,
+                        ErrorPolicy::SkipFailedRows,
+                        &self.sender,
+                        &self.progress_sender,
+                        &mut vec![None; tables.len()],
+                        &mut issues,
+                    )
+                    .await;
+                    let res = Table::transaction_rollback(connection.as_mut()).await;
+                    let is_rolled_back = res.result.is_ok();
+                    self.log(res).await;
+                    if is_rolled_back {
+                        Ok(issues.unwrap_or_default())
+                    } else {
+                        Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                            "Rolling back the test failed",
+                        ) as Box<dyn Error + Send>)
                     }
-                    oneshot_sender
-                        .send(true)
-                        .unwrap_or_else(|_| println!("Failed to send end of append transaction"));
                 }
-                Communication::TryCommit(sender) => {
+                None => Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "Couldn't start the test, see the log",
+                ) as Box<dyn Error + Send>),
+            },
+            Err(e) => Err(e),
+        };
+        report_sender
+            .send(report)
+            .unwrap_or_else(|_| println!("db - failed to send the test report"));
+    }
+    /* Appends through `workers` connections at once without a transaction,
+     * every batch is committed as soon as it's in */
+    pub async fn append_in_parallel(
+        self,
+        workers: usize,
+        finished_sender: oneshot::Sender<Vec<usize>>,
+    ) {
+        if self.is_transaction_pending("-- parallel append").await {
+            return self.skip_all(finished_sender);
+        }
+        let mut connections: Vec<Box<dyn DBConnection>> = Vec::new();
+        for _ in 0..workers {
+            match self.acquire().await {
+                Some(connection) => connections.push(connection),
+                None => break,
+            }
+        }
+        if connections.is_empty() {
+            return self.skip_all(finished_sender);
+        }
+        println!("Appending through {} connections...", connections.len());
+        let mut inserted_keys = Some(Vec::new());
+        let skipped_rows = insert_rows_in_parallel(
+            &mut connections,
+            &self.table,
+            &self.csv_data,
+            self.error_policy,
+            &self.sender,
+            &self.progress_sender,
+            &mut inserted_keys,
+        )
+        .await;
+        /* there's no commit to wait for */
+        if let Some(keys) = inserted_keys.filter(|keys| !keys.is_empty()) {
+            if let Some(record) = record_import(
+                connections[0].as_mut(),
+                &self.table,
+                self.database.clone(),
+                keys,
+            )
+            .await
+            {
+                ImportJournal::add(record)
+                    .unwrap_or_else(|e| println!("Failed to save the import journal, {}", e));
+            }
+        }
+        finished_sender
+            .send(skipped_rows)
+            .unwrap_or_else(|_| println!("db - failed to send end of append"));
+    }
+    /* Updates rows that match on `key_fields`, inserts the rest */
+    pub async fn upsert(
+        self,
+        key_fields: Vec<String>,
+        finished_sender: oneshot::Sender<Vec<usize>>,
+    ) {
+        /* upserts overwrite rows, so they get a snapshot as well */
+        let mut connection = match self.begin(true).await {
+            Some(connection) => connection,
+            None => return self.skip_all(finished_sender),
+        };
+        /* ON DUPLICATE KEY/ON CONFLICT only work if the keys are a unique index */
+        let has_unique_index = match connection.unique_key_sets(&self.table.name).await {
+            Ok(key_sets) => key_sets.iter().any(|key_set| {
+                key_set.len() == key_fields.len()
+                    && key_set.iter().all(|field| key_fields.contains(field))
+            }),
+            Err(e) => {
+                println!("db - failed to query unique indexes, {}", e);
+                false
+            }
+        };
+        println!(
+            "Upserting on ({}), unique index found: {}",
+            key_fields.join(", "),
+            has_unique_index
+        );
+        let skipped_rows = if has_unique_index {
+            insert_rows(
+                connection.as_mut(),
+                &self.table,
+                &self.csv_data,
+                Some(&key_fields),
+                self.error_policy,
+                &self.sender,
+                &self.progress_sender,
+                &mut None,
+            )
+            .await
+        } else {
+            upsert_rows_by_select(
+                connection.as_mut(),
+                &self.table,
+                &self.csv_data,
+                &key_fields,
+                self.error_policy,
+                &self.sender,
+                &self.progress_sender,
+            )
+            .await
+        };
+        self.keep_open(connection, Vec::new(), skipped_rows, finished_sender)
+            .await;
+    }
+    async fn acquire(&self) -> Option<Box<dyn DBConnection>> {
+        match self.pool.acquire().await {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                self.log(QueryResult {
+                    query: "-- connect".to_owned(),
+                    result: Err(Box::new(e)),
+                })
+                .await;
+                None
+            }
+        }
+    }
+    /* Takes a connection and starts the transaction on it, after snapshotting the table
+     * if `with_snapshot`. CREATE TABLE commits implicitly in mysql, so the snapshot comes first */
+    async fn begin(&self, with_snapshot: bool) -> Option<Box<dyn DBConnection>> {
+        if self.is_transaction_pending("BEGIN").await {
+            return None;
+        }
+        let mut connection = self.acquire().await?;
+        if with_snapshot {
+            let snapshot = self.table.create_snapshot(connection.as_mut()).await;
+            let is_snapshot_failed = snapshot.result.is_err();
+            self.log(snapshot).await;
+            if is_snapshot_failed {
+                return None;
+            }
+        }
+        /* without a transaction every insert would commit on its own */
+        let trans_start = Table::start_transaction(connection.as_mut()).await;
+        let is_trans_start_failed = trans_start.result.is_err();
+        self.log(trans_start).await;
+        if is_trans_start_failed {
+            return None;
+        }
+        Some(connection)
+    }
+    /* Rows an earlier insert left uncommitted are the user's to commit or roll back,
+     * and their locks would stall this one, so nothing new starts until that's done */
+    async fn is_transaction_pending(&self, query: &str) -> bool {
+        if self.open_transaction.lock().await.is_none() {
+            return false;
+        }
+        self.log(QueryResult {
+            query: query.to_owned(),
+            result: Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                "An earlier import is still waiting, commit or roll it back first",
+            )),
+        })
+        .await;
+        true
+    }
+    /* Leaves the transaction open on `connection` for the user to commit or roll back */
+    async fn keep_open(
+        &self,
+        mut connection: Box<dyn DBConnection>,
+        pending_imports: Vec<ImportRecord>,
+        skipped_rows: Vec<usize>,
+        finished_sender: oneshot::Sender<Vec<usize>>,
+    ) {
+        let mut open_transaction = self.open_transaction.lock().await;
+        /* another insert left one open while this one ran, that one's rows stay the user's call */
+        if open_transaction.is_some() {
+            drop(open_transaction);
+            let res = Table::transaction_rollback(connection.as_mut()).await;
+            self.log(res).await;
+            self.log(QueryResult {
+                query: "-- keep open".to_owned(),
+                result: Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "Another import was left open meanwhile, this one was rolled back",
+                )),
+            })
+            .await;
+            return self.skip_all(finished_sender);
+        }
+        *open_transaction = Some(OpenTransaction {
+            connection,
+            pending_imports,
+        });
+        drop(open_transaction);
+        finished_sender
+            .send(skipped_rows)
+            .unwrap_or_else(|_| println!("db - failed to send end of insert into table"));
+    }
+    /* Nothing got sent, so every row is still left to insert */
+    fn skip_all(&self, finished_sender: oneshot::Sender<Vec<usize>>) {
+        finished_sender
+            .send((usize::from(self.csv_data.are_headers)..self.csv_data.data.rows()).collect())
+            .unwrap_or_else(|_| println!("db - failed to send end of insert into table"));
+    }
+    async fn log(&self, res: QueryResult) {
+        println!(
+            "      | Query: {}\n       > Result: {:?}",
+            res.query, res.result
+        );
+        self.sender
+            .send(res)
+            .await
+            .unwrap_or_else(|_| println!("db - failed to send query result"));
+    }
+}
+/* Inserts all csv rows in batches, retrying the rows of a failed batch one by one
+ * so the failing ones show up in the log. Progress is reported as (inserted, total).
+ * With `upsert_key_fields` rows colliding on those keys update the existing ones.
+ * `inserted_keys` collects the primary keys of every inserted row, it ends up None
+ * if they can't all be told. Returns the grid indexes of rows that didn't make it in */
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_rows(
+    connection: &mut dyn DBConnection,
+    table: &Table,
+    csv_data: &ImportedData,
+    upsert_key_fields: Option<&[String]>,
+    error_policy: ErrorPolicy,
+    sender: &Sender<QueryResult>,
+    progress_sender: &watch::Sender<(usize, usize)>,
+    inserted_keys: &mut Option<Vec<String>>,
+) -> Vec<usize> {
+    let start_i: usize = csv_data.are_headers.into();
+    let rows = grid_rows(csv_data);
+    let queue = InsertQueue::plan(connection, &rows, error_policy, true).await;
+    let failed_rows = insert_batches(
+        connection,
+        table,
+        &rows,
+        &queue,
+        upsert_key_fields,
+        sender,
+        progress_sender,
+        inserted_keys,
+    )
+    .await;
+    queue.finish(sender, failed_rows, start_i).await
+}
+/* Same as insert_rows without upserting, but every connection works through the batches
+ * at the same time. Each batch commits on its own, so there's no transaction to roll back */
+pub async fn insert_rows_in_parallel(
+    connections: &mut [Box<dyn DBConnection>],
+    table: &Table,
+    csv_data: &ImportedData,
+    error_policy: ErrorPolicy,
+    sender: &Sender<QueryResult>,
+    progress_sender: &watch::Sender<(usize, usize)>,
+    inserted_keys: &mut Option<Vec<String>>,
+) -> Vec<usize> {
+    let start_i: usize = csv_data.are_headers.into();
+    let rows = grid_rows(csv_data);
+    let queue = InsertQueue::plan(connections[0].as_mut(), &rows, error_policy, false).await;
+    let is_tracking_keys = inserted_keys.is_some();
+    let workers = connections.iter_mut().map(|connection| {
+        let (rows, queue) = (&rows, &queue);
+        async move {
+            let mut worker_keys = if is_tracking_keys {
+                Some(Vec::new())
+            } else {
+                None
+            };
+            let failed_rows = insert_batches(
+                connection.as_mut(),
+                table,
+                rows,
+                queue,
+                None,
+                sender,
+                progress_sender,
+                &mut worker_keys,
+            )
+            .await;
+            (failed_rows, worker_keys)
+        }
+    });
+    let mut failed_rows = Vec::new();
+    for (worker_failed_rows, worker_keys) in join_all(workers).await {
+        failed_rows.extend(worker_failed_rows);
+        match (inserted_keys.as_mut(), worker_keys) {
+            (Some(keys), Some(worker_keys)) => keys.extend(worker_keys),
+            _ => *inserted_keys = None,
+        }
+    }
+    failed_rows.sort_unstable();
+    queue.finish(sender, failed_rows, start_i).await
+}
+/* The statements an import of `csv_data` into `table` would run, written down by `script`
+ * through the same Table methods. Stops at the first row that can't be turned into SQL */
+pub async fn write_script(
+    script: &mut ScriptConnection,
+    table: &Table,
+    csv_data: &ImportedData,
+    options: &ScriptOptions,
+) -> Result<(), Box<dyn Error + Send>> {
+    let csv_rows = grid_rows(csv_data);
+    script.comment(&format!(
+        "{} of {} rows into {}, exported on {}",
+        options.kind.name(),
+        csv_rows.len(),
+        table.name,
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    if options.with_transaction {
+        Table::start_transaction(script).await.result?;
+    }
+    if options.kind == ScriptKind::Replace {
+        table.truncate_table(script).await.result?;
+    }
+    for batch in csv_rows.chunks(options.rows_per_insert.max(1)) {
+        let res = match options.kind {
+            ScriptKind::Upsert => {
+                table
+                    .upsert_rows_into_table(script, batch.to_vec(), &options.key_fields)
+                    .await
+            }
+            ScriptKind::Append | ScriptKind::Replace => {
+                table.insert_rows_into_table(script, batch.to_vec()).await
+            }
+        };
+        res.result?;
+    }
+    if options.with_transaction {
+        Table::transaction_commit(script).await.result?;
+    }
+    Ok(())
+}
+fn grid_rows(csv_data: &ImportedData) -> Vec<Vec<&DataEntry>> {
+    let start_i: usize = csv_data.are_headers.into();
+    (start_i..csv_data.data.rows())
+        .map(|i| csv_data.data[i].iter().collect())
+        .collect()
+}
+/* The batches of one insert, handed out to whichever connection is free next */
+struct InsertQueue {
+    batches: Vec<Range<usize>>,
+    row_count: usize,
+    error_policy: ErrorPolicy,
+    /* inside a transaction every insert needs a savepoint to return to */
+    use_savepoints: bool,
+    next_batch: AtomicUsize,
+    done_rows: AtomicUsize,
+    failed_rows: AtomicUsize,
+    is_aborted: AtomicBool,
+}
+impl InsertQueue {
+    async fn plan(
+        connection: &mut dyn DBConnection,
+        rows: &[Vec<&DataEntry>],
+        error_policy: ErrorPolicy,
+        use_savepoints: bool,
+    ) -> InsertQueue {
+        let max_packet_size = match connection.max_packet_size().await {
+            Ok(size) => size,
+            Err(e) => {
+                println!("db - failed to query max packet size, {}", e);
+                None
+            }
+        };
+        let batches = plan_insert_batches(rows, max_packet_size, connection.max_bind_values());
+        println!(
+            "Inserting {} rows in {} batches...",
+            rows.len(),
+            batches.len()
+        );
+        InsertQueue {
+            batches,
+            row_count: rows.len(),
+            error_policy,
+            use_savepoints,
+            next_batch: AtomicUsize::new(0),
+            done_rows: AtomicUsize::new(0),
+            failed_rows: AtomicUsize::new(0),
+            is_aborted: AtomicBool::new(false),
+        }
+    }
+    fn next_batch(&self) -> Option<Range<usize>> {
+        if self.is_aborted.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.batches
+            .get(self.next_batch.fetch_add(1, Ordering::SeqCst))
+            .cloned()
+    }
+    /* Counts a failed row, true once the error policy says to stop */
+    fn fail_row(&self) -> bool {
+        let failed_rows = self.failed_rows.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.error_policy.should_abort(failed_rows) {
+            self.is_aborted.store(true, Ordering::SeqCst);
+        }
+        self.is_aborted.load(Ordering::SeqCst)
+    }
+    fn finish_batch(&self, batch: &Range<usize>, progress_sender: &watch::Sender<(usize, usize)>) {
+        let done_rows = self.done_rows.fetch_add(batch.len(), Ordering::SeqCst) + batch.len();
+        progress_sender.send_replace((done_rows, self.row_count));
+    }
+    /* Failed rows as grid indexes, plus the rows nobody got to if it was aborted */
+    async fn finish(
+        &self,
+        sender: &Sender<QueryResult>,
+        failed_rows: Vec<usize>,
+        start_i: usize,
+    ) -> Vec<usize> {
+        let skipped_rows: Vec<usize> = failed_rows.into_iter().map(|i| start_i + i).collect();
+        if !self.is_aborted.load(Ordering::SeqCst) {
+            return skipped_rows;
+        }
+        let remaining_rows = self
+            .batches
+            .get(self.next_batch.load(Ordering::SeqCst))
+            .map_or(self.row_count, |batch| batch.start);
+        abort_inserting(
+            sender,
+            skipped_rows,
+            start_i + remaining_rows..start_i + self.row_count,
+        )
+        .await
+    }
+}
+/* Works through `queue` until it's empty or aborted,
+ * returning the indexes into `rows` that failed or were cut short */
+#[allow(clippy::too_many_arguments)]
+async fn insert_batches(
+    connection: &mut dyn DBConnection,
+    table: &Table,
+    rows: &[Vec<&DataEntry>],
+    queue: &InsertQueue,
+    upsert_key_fields: Option<&[String]>,
+    sender: &Sender<QueryResult>,
+    progress_sender: &watch::Sender<(usize, usize)>,
+    inserted_keys: &mut Option<Vec<String>>,
+) -> Vec<usize> {
+    let mut failed_rows: Vec<usize> = Vec::new();
+    while let Some(batch) = queue.next_batch() {
+        let (res, generated_keys) = insert_with_savepoint(
+            connection,
+            table,
+            rows[batch.clone()].to_vec(),
+            upsert_key_fields,
+            generated_key_field(inserted_keys, table, &rows[batch.clone()]),
+            queue.use_savepoints,
+        )
+        .await;
+        let is_failed_batch = res.result.is_err();
+        println!(
+            "      | Query: {}\n       > Result: {:?}",
+            res.query, res.result
+        );
+        if res.result.is_ok() {
+            track_inserted_keys(inserted_keys, table, &rows[batch.clone()], generated_keys);
+        }
+        sender
+            .send(res)
+            .await
+            .unwrap_or_else(|_| println!("db - failed to send insert into table"));
+
+        if is_failed_batch {
+            for row_i in batch.clone() {
+                /* a single row batch already failed on its own */
+                let is_failed_row = batch.len() == 1 || {
+                    let (res, generated_keys) = insert_with_savepoint(
+                        connection,
+                        table,
+                        vec![rows[row_i].clone()],
+                        upsert_key_fields,
+                        generated_key_field(inserted_keys, table, &rows[row_i..row_i + 1]),
+                        queue.use_savepoints,
+                    )
+                    .await;
+                    let is_failed_row = res.result.is_err();
+                    if res.result.is_ok() {
+                        track_inserted_keys(
+                            inserted_keys,
+                            table,
+                            &rows[row_i..row_i + 1],
+                            generated_keys,
+                        );
+                    }
                     sender
-                        .send(Table::transaction_commit(self.db_connection.as_mut().unwrap()).await)
-                        .unwrap_or_else(|_| println!("Failed to respond to TryCommit"));
+                        .send(res)
+                        .await
+                        .unwrap_or_else(|_| println!("db - failed to send insert into table"));
+                    is_failed_row
+                };
+                if is_failed_row {
+                    failed_rows.push(row_i);
+                    if queue.fail_row() {
+                        /* the rest of this batch never got tried */
+                        failed_rows.extend(row_i + 1..batch.end);
+                        return failed_rows;
+                    }
                 }
-                Communication::TryRollBack(sender) => {
-                    sender
-                        .send(
-                            Table::transaction_rollback(self.db_connection.as_mut().unwrap()).await,
-                        )
-                        .unwrap_or_else(|_| println!("Failed to respond to TryRollBack"));
+            }
+        }
+        queue.finish_batch(&batch, progress_sender);
+    }
+    failed_rows
+}
+/* The appended rows as they are before commit, to tell later whether they changed since */
+async fn record_import(
+    connection: &mut dyn DBConnection,
+    table: &Table,
+    database: String,
+    keys: Vec<String>,
+) -> Option<ImportRecord> {
+    let key_field = primary_key_field(table)?.description.field.clone();
+    let result_set = match table
+        .query_rows_by_keys(connection, &key_field, &keys)
+        .await
+    {
+        Ok(result_set) => result_set,
+        Err(e) => {
+            println!("db - failed to query the appended rows, {}", e);
+            return None;
+        }
+    };
+    /* the keys were guessed wrong if other rows came back */
+    if result_set.rows.len() != keys.len() {
+        println!(
+            "Found {} of {} appended rows, this import won't be journaled",
+            result_set.rows.len(),
+            keys.len()
+        );
+        return None;
+    }
+    Some(ImportRecord {
+        id: 0,
+        database,
+        table: table.name.clone(),
+        time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        key_field,
+        columns: result_set.columns,
+        rows: result_set.rows,
+        is_undone: false,
+    })
+}
+/* The primary key to read back from the insert of `rows`, when keys are tracked
+ * and none of the rows brings its own */
+fn generated_key_field<'a>(
+    inserted_keys: &Option<Vec<String>>,
+    table: &'a Table,
+    rows: &[Vec<&DataEntry>],
+) -> Option<&'a str> {
+    inserted_keys.as_ref()?;
+    let key_field = primary_key_field(table)?;
+    let has_grid_keys = key_field.mapped_to_col.is_some_and(|col| {
+        rows.iter()
+            .any(|row| row.get(col).is_some_and(|cell| !cell.data.is_empty()))
+    });
+    if has_grid_keys {
+        None
+    } else {
+        Some(&key_field.description.field)
+    }
+}
+/* Adds the keys of freshly inserted `rows`, from the grid when the primary key is mapped
+ * and from `generated_keys` when the database generated them. If any row's key can't be told
+ * the tracking stops for good, a journal missing rows would only undo part of an import */
+fn track_inserted_keys(
+    inserted_keys: &mut Option<Vec<String>>,
+    table: &Table,
+    rows: &[Vec<&DataEntry>],
+    generated_keys: Option<Vec<String>>,
+) {
+    if inserted_keys.is_none() {
+        return;
+    }
+    let new_keys = primary_key_field(table).and_then(|key_field| {
+        let grid_keys: Vec<String> = match key_field.mapped_to_col {
+            Some(col) => rows
+                .iter()
+                .filter_map(|row| row.get(col))
+                .map(|cell| cell.data.clone())
+                .filter(|key| !key.is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+        if grid_keys.len() == rows.len() {
+            Some(grid_keys)
+        } else if grid_keys.is_empty() {
+            /* every key was left for the database to generate */
+            generated_keys.filter(|keys| keys.len() == rows.len())
+        } else {
+            None
+        }
+    });
+    match (inserted_keys.as_mut(), new_keys) {
+        (Some(keys), Some(new_keys)) => keys.extend(new_keys),
+        _ => {
+            println!("Can't tell the keys of the inserted rows, this import won't be journaled");
+            *inserted_keys = None;
+        }
+    }
+}
+/* The table's primary key, if it's a single field */
+pub fn primary_key_field(table: &Table) -> Option<&TableField> {
+    let mut key_fields = table
+        .fields
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter(|field| field.description.key == "PRI");
+    match (key_fields.next(), key_fields.next()) {
+        (Some(key_field), None) => Some(key_field),
+        _ => None,
+    }
+}
+/* Postgres aborts the whole transaction on an error, so inside one every insert
+ * gets a savepoint to return to. Outside of a transaction a failed insert changes nothing */
+async fn insert_with_savepoint(
+    connection: &mut dyn DBConnection,
+    table: &Table,
+    csv_rows: Vec<Vec<&DataEntry>>,
+    upsert_key_fields: Option<&[String]>,
+    generated_key_field: Option<&str>,
+    use_savepoint: bool,
+) -> (QueryResult, Option<Vec<String>>) {
+    if use_savepoint {
+        let savepoint = Table::savepoint(connection, "csql_insert").await;
+        if savepoint.result.is_err() {
+            return (savepoint, None);
+        }
+    }
+    let (res, generated_keys) = match (upsert_key_fields, generated_key_field) {
+        (Some(key_fields), _) => (
+            table
+                .upsert_rows_into_table(connection, csv_rows, key_fields)
+                .await,
+            None,
+        ),
+        (None, Some(key_field)) => {
+            table
+                .insert_rows_returning_keys(connection, csv_rows, key_field)
+                .await
+        }
+        (None, None) => (
+            table.insert_rows_into_table(connection, csv_rows).await,
+            None,
+        ),
+    };
+    if !use_savepoint {
+        return (res, generated_keys);
+    }
+    if res.result.is_ok() {
+        Table::release_savepoint(connection, "csql_insert").await;
+    } else {
+        Table::rollback_to_savepoint(connection, "csql_insert").await;
+    }
+    (res, generated_keys)
+}
+/* Upserts every row on its own through SELECT + UPDATE/INSERT,
+ * for when there's no unique index matching `key_fields` */
+pub async fn upsert_rows_by_select(
+    connection: &mut dyn DBConnection,
+    table: &Table,
+    csv_data: &ImportedData,
+    key_fields: &[String],
+    error_policy: ErrorPolicy,
+    sender: &Sender<QueryResult>,
+    progress_sender: &watch::Sender<(usize, usize)>,
+) -> Vec<usize> {
+    let start_i: usize = csv_data.are_headers.into();
+    let total_rows = csv_data.data.rows() - start_i;
+    let mut skipped_rows: Vec<usize> = Vec::new();
+    progress_sender.send_replace((0, total_rows));
+    for i in start_i..csv_data.data.rows() {
+        let row: Vec<&DataEntry> = csv_data.data[i].iter().collect();
+        let savepoint = Table::savepoint(connection, "csql_upsert").await;
+        let res = if savepoint.result.is_err() {
+            savepoint
+        } else {
+            let res = table
+                .upsert_row_by_select(connection, row, key_fields)
+                .await;
+            if res.result.is_ok() {
+                Table::release_savepoint(connection, "csql_upsert").await;
+            } else {
+                Table::rollback_to_savepoint(connection, "csql_upsert").await;
+            }
+            res
+        };
+        let is_failed_row = res.result.is_err();
+        println!(
+            "      | Query: {}\n       > Result: {:?}",
+            res.query, res.result
+        );
+        sender
+            .send(res)
+            .await
+            .unwrap_or_else(|_| println!("db - failed to send upsert into table"));
+        progress_sender.send_replace((i + 1 - start_i, total_rows));
+
+        if is_failed_row {
+            skipped_rows.push(i);
+            if error_policy.should_abort(skipped_rows.len()) {
+                return abort_inserting(sender, skipped_rows, i + 1..csv_data.data.rows()).await;
+            }
+        }
+    }
+    skipped_rows
+}
+/* Inserts every row into all of `tables`, which have to be in dependency order, one row at a time
+ * so the keys a parent gets can fill in its children's foreign keys. A row goes in whole or not
+ * at all. `inserted_keys` collects the primary keys per table like in insert_rows.
+ * With `issues` every error and warning is collected there as well */
+#[allow(clippy::too_many_arguments)]
+async fn insert_rows_into_tables(
+    connection: &mut dyn DBConnection,
+    tables: &[Table],
+    csv_data: &ImportedData,
+    error_policy: ErrorPolicy,
+    sender: &Sender<QueryResult>,
+    progress_sender: &watch::Sender<(usize, usize)>,
+    inserted_keys: &mut [Option<Vec<String>>],
+    issues: &mut Option<Vec<RowIssue>>,
+) -> Vec<usize> {
+    let start_i: usize = csv_data.are_headers.into();
+    let total_rows = csv_data.data.rows() - start_i;
+    let mut skipped_rows: Vec<usize> = Vec::new();
+    progress_sender.send_replace((0, total_rows));
+    for i in start_i..csv_data.data.rows() {
+        let csv_row = &csv_data.data[i];
+        let savepoint = Table::savepoint(connection, "csql_row").await;
+        let mut is_failed_row = savepoint.result.is_err();
+        if is_failed_row {
+            sender
+                .send(savepoint)
+                .await
+                .unwrap_or_else(|_| println!("db - failed to send insert into table"));
+        }
+        /* what every table of this row got, keyed by (table, field) */
+        let mut row_values: HashMap<(String, String), String> = HashMap::new();
+        let mut row_keys: Vec<Option<String>> = vec![None; tables.len()];
+        for (table_i, table) in tables.iter().enumerate() {
+            if is_failed_row {
+                break;
+            }
+            let cells = table.row_cells(csv_row, &row_values);
+            if cells.is_empty() {
+                continue;
+            }
+            let res = table
+                .insert_rows_into_table(connection, vec![cells.iter().collect()])
+                .await;
+            println!(
+                "      | Query: {}\n       > Result: {:?}",
+                res.query, res.result
+            );
+            if let Ok(qr) = &res.result {
+                for cell in cells.iter() {
+                    let field = cell.curr_field_description.as_ref().unwrap().field.clone();
+                    row_values.insert((table.name.clone(), field), cell.data.clone());
+                }
+                if let Some(key_field) = primary_key_field(table) {
+                    let key_name = &key_field.description.field;
+                    let key = (table.name.clone(), key_name.clone());
+                    if row_values.get(&key).is_none_or(|value| value.is_empty()) {
+                        match connection.last_insert_id(qr, &table.name, key_name).await {
+                            Ok(Some(id)) => {
+                                row_values.insert(key.clone(), id.to_string());
+                            }
+                            Ok(None) => {}
+                            Err(e) => println!(
+                                "db - failed to query the key of the row inserted into \"{}\", {}",
+                                table.name, e
+                            ),
+                        }
+                    }
+                    row_keys[table_i] = row_values.get(&key).cloned();
                 }
             }
+            is_failed_row = res.result.is_err();
+            if let Some(issues) = issues.as_mut() {
+                match &res.result {
+                    Ok(_) => match connection.fetch_warnings().await {
+                        Ok(warnings) => issues.extend(
+                            warnings
+                                .into_iter()
+                                .map(|warning| RowIssue::new(table, i, warning, false)),
+                        ),
+                        Err(e) => println!("db - failed to query warnings, {}", e),
+                    },
+                    Err(e) => issues.push(RowIssue::new(table, i, e.to_string(), true)),
+                }
+            }
+            sender
+                .send(res)
+                .await
+                .unwrap_or_else(|_| println!("db - failed to send insert into table"));
+        }
+        if is_failed_row {
+            Table::rollback_to_savepoint(connection, "csql_row").await;
+        } else {
+            Table::release_savepoint(connection, "csql_row").await;
+            for (keys, key) in inserted_keys.iter_mut().zip(row_keys) {
+                match (keys.as_mut(), key) {
+                    (Some(keys), Some(key)) => keys.push(key),
+                    _ => *keys = None,
+                }
+            }
+        }
+        progress_sender.send_replace((i + 1 - start_i, total_rows));
+
+        if is_failed_row {
+            skipped_rows.push(i);
+            if error_policy.should_abort(skipped_rows.len()) {
+                return abort_inserting(sender, skipped_rows, i + 1..csv_data.data.rows()).await;
+            }
         }
     }
+    skipped_rows
+}
+/* Logs why inserting stopped, the rows it never got to count as skipped too */
+async fn abort_inserting(
+    sender: &Sender<QueryResult>,
+    mut skipped_rows: Vec<usize>,
+    remaining_rows: Range<usize>,
+) -> Vec<usize> {
+    let message = format!(
+        "Stopped after {} failed rows, {} rows weren't attempted",
+        skipped_rows.len(),
+        remaining_rows.len()
+    );
+    println!("{}", message);
+    sender
+        .send(QueryResult {
+            query: "-- aborted".to_owned(),
+            result: Err(<String as Into<Box<dyn std::error::Error + Send + Sync>>>::into(message)),
+        })
+        .await
+        .unwrap_or_else(|_| println!("db - failed to send abort of insert"));
+    skipped_rows.extend(remaining_rows);
+    skipped_rows
 }
 pub fn is_whole_table_parsed(csv_data: &MutexGuard<ImportedData>) -> bool {
     if csv_data.data.cols() == csv_data.parsed_cols.len() {
@@ -209,6 +2178,70 @@ pub fn is_whole_table_parsed(csv_data: &MutexGuard<ImportedData>) -> bool {
         false
     }
 }
+/* Flags rows whose `key_fields` values repeat an earlier row or one of `existing_keys`.
+ * Cells that already failed parsing keep their own error */
+pub fn mark_unique_conflicts(
+    csv_data: &mut ImportedData,
+    key_fields: &[&TableField],
+    existing_keys: &HashSet<Vec<String>>,
+    table_name: &str,
+) {
+    let cols: Vec<usize> = key_fields
+        .iter()
+        .map(|field| field.mapped_to_col.unwrap())
+        .collect();
+    let key_names = key_fields
+        .iter()
+        .map(|field| field.description.field.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+
+    let mut first_rows: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut conflicts: Vec<(usize, String)> = Vec::new();
+    let start_i: usize = csv_data.are_headers.into();
+    for row in start_i..csv_data.data.rows() {
+        let cells: Vec<&DataEntry> = cols.iter().map(|col| &csv_data.data[row][*col]).collect();
+        let has_null = key_fields
+            .iter()
+            .zip(cells.iter())
+            .any(|(field, cell)| cell.data.is_empty() && field.description.null == "YES");
+        if has_null {
+            continue;
+        }
+        let key: Vec<String> = key_fields
+            .iter()
+            .zip(cells.iter())
+            .map(|(field, cell)| normalized_key(&field.description, Some(&cell.data)))
+            .collect();
+
+        if existing_keys.contains(&key) {
+            conflicts.push((
+                row,
+                format!("({}) already exists in table \"{}\"", key_names, table_name),
+            ));
+        }
+        match first_rows.get(&key) {
+            Some(first_row) => {
+                conflicts.push((row, format!("Same ({}) as row {}", key_names, first_row)));
+                conflicts.push((*first_row, format!("Same ({}) as row {}", key_names, row)));
+            }
+            None => {
+                first_rows.insert(key, row);
+            }
+        }
+    }
+
+    for (row, message) in conflicts {
+        for col in cols.iter() {
+            let cell = &mut csv_data.data[row][*col];
+            if matches!(cell.is_parsed, Some(Ok(()))) {
+                cell.is_parsed = Some(Err(Arc::from(<String as Into<
+                    Box<dyn std::error::Error + Send + Sync>,
+                >>::into(message.clone()))));
+            }
+        }
+    }
+}
 pub fn is_whole_col_parsed(csv_data: &mut MutexGuard<ImportedData>, col_index: usize) -> bool {
     let mut csv_iter = csv_data.data.iter_col(col_index);
     if csv_data.are_headers {
@@ -288,19 +2321,95 @@ pub fn try_match_headers_to_fields(
 
 #[derive(Debug)]
 pub enum Communication {
+    /* master passphrase and the profile store's salt */
+    DeriveMasterKey(String, Vec<u8>, oneshot::Sender<MasterKey>),
     ValidateCreditentials(
         DBLoginData,
         oneshot::Sender<Result<(), Box<dyn std::error::Error + Send>>>,
     ),
     LoadImportFilePath(String),
     GetTableDescription(usize),
-    ImportDBEntries,
+    ImportDBEntries(EntriesFilter),
     RemoveCol(usize),
     RemoveRow(usize),
-    SaveCSV(String),
+    SaveCSV(String, CsvDialect),
+    ExportSQLScript(String, ScriptOptions),
+    /* the CREATE TABLE for the grid under the given name, to be edited before CreateTable */
+    ProposeTable(String, oneshot::Sender<String>),
+    CreateTable(String, oneshot::Sender<QueryResult>),
+    /* the bool runs it inside the import's open transaction */
+    RunQuery(
+        String,
+        bool,
+        oneshot::Sender<Result<ConsoleOutput, Box<dyn std::error::Error + Send>>>,
+    ),
+    /* replaces the grid with a console result */
+    LoadResultSet(TextResultSet),
+    /* an ALTER TABLE for every given col, adding it to the working table */
+    ProposeColumns(
+        Vec<usize>,
+        oneshot::Sender<Result<Vec<String>, Box<dyn std::error::Error + Send>>>,
+    ),
+    /* runs the proposed statements, then maps the new fields to the cols */
+    AddColumns(Vec<String>, Vec<usize>, oneshot::Sender<Vec<QueryResult>>),
     TryParseCol(usize),
-    StartAppending(Sender<QueryResult>, oneshot::Sender<bool>),
-    StartInserting(Sender<QueryResult>, oneshot::Sender<bool>),
+    CheckForeignKeys,
+    CheckUniqueKeys,
+    /* the usize is how many connections insert at once, more than one skips the transaction */
+    StartAppending(
+        ErrorPolicy,
+        InsertOptions,
+        usize,
+        Sender<QueryResult>,
+        watch::Sender<(usize, usize)>,
+        oneshot::Sender<Vec<usize>>,
+    ),
+    StartInserting(
+        ErrorPolicy,
+        InsertOptions,
+        Sender<QueryResult>,
+        watch::Sender<(usize, usize)>,
+        oneshot::Sender<Vec<usize>>,
+    ),
+    /* appends to the working table and the linked ones */
+    StartAppendingToTables(
+        ErrorPolicy,
+        InsertOptions,
+        Sender<QueryResult>,
+        watch::Sender<(usize, usize)>,
+        oneshot::Sender<Vec<usize>>,
+    ),
+    StartUpserting(
+        Vec<String>,
+        ErrorPolicy,
+        InsertOptions,
+        Sender<QueryResult>,
+        watch::Sender<(usize, usize)>,
+        oneshot::Sender<Vec<usize>>,
+    ),
+    /* appends like StartAppendingToTables, then always rolls back */
+    StartTestImport(
+        InsertOptions,
+        Sender<QueryResult>,
+        watch::Sender<(usize, usize)>,
+        oneshot::Sender<Result<Vec<RowIssue>, Box<dyn std::error::Error + Send>>>,
+    ),
+    MarkRowIssues(Vec<RowIssue>),
+    KeepOnlyRows(Vec<usize>),
+    DiffWithTable(
+        String,
+        oneshot::Sender<Result<TableDiff, Box<dyn std::error::Error + Send>>>,
+    ),
+    ListSnapshots(oneshot::Sender<Result<Vec<String>, Box<dyn std::error::Error + Send>>>),
+    RestoreSnapshot(String, oneshot::Sender<QueryResult>),
+    DeleteSnapshot(String, oneshot::Sender<QueryResult>),
+    ListImports(oneshot::Sender<Vec<ImportRecord>>),
+    /* (rows changed since the import, rows already gone) */
+    CheckImportUndo(
+        u64,
+        oneshot::Sender<Result<(usize, usize), Box<dyn std::error::Error + Send>>>,
+    ),
+    UndoImport(u64, oneshot::Sender<QueryResult>),
     TryCommit(oneshot::Sender<QueryResult>),
     TryRollBack(oneshot::Sender<QueryResult>),
 }
@@ -0,0 +1,240 @@
+use super::csv_handler::ImportedData;
+use super::db_connection::DBDriver;
+use chrono::prelude::{NaiveDate, NaiveDateTime};
+
+/* Widest varchar proposed, longer cols become text */
+const MAX_VARCHAR_LENGTH: usize = 255;
+/* mysql's limit for decimal precision */
+const MAX_DECIMAL_PRECISION: usize = 65;
+
+/* A CREATE TABLE with a field per col of the grid, typed after what the col holds */
+pub fn create_table_statement(
+    table_name: &str,
+    csv_data: &ImportedData,
+    driver: DBDriver,
+) -> String {
+    let fields: Vec<String> = column_names(csv_data)
+        .iter()
+        .enumerate()
+        .map(|(col, name)| {
+            format!(
+                "    {}",
+                field_definition(csv_data, col, name, driver, true)
+            )
+        })
+        .collect();
+    format!(
+        "CREATE TABLE {} (\n{}\n)",
+        identifier(table_name),
+        fields.join(",\n")
+    )
+}
+
+/* An ALTER TABLE per col in `cols`, sqlite can only add one field at a time.
+ * The new fields are always nullable, the rows already in the table have no value for them */
+pub fn add_columns_statements(
+    table_name: &str,
+    csv_data: &ImportedData,
+    cols: &[usize],
+    driver: DBDriver,
+) -> Vec<String> {
+    let names = column_names(csv_data);
+    cols.iter()
+        .filter(|col| **col < names.len())
+        .map(|col| {
+            format!(
+                "ALTER TABLE {} ADD COLUMN {}",
+                table_name,
+                field_definition(csv_data, *col, &names[*col], driver, false)
+            )
+        })
+        .collect()
+}
+
+/* "name type NULL" for the values of `col`, NOT NULL only if `allows_not_null` and none is empty */
+fn field_definition(
+    csv_data: &ImportedData,
+    col: usize,
+    name: &str,
+    driver: DBDriver,
+    allows_not_null: bool,
+) -> String {
+    let start_i: usize = csv_data.are_headers.into();
+    let values: Vec<&str> = (start_i..csv_data.data.rows())
+        .map(|row| csv_data.data[row][col].data.as_str())
+        .collect();
+    let null = if allows_not_null && values.iter().all(|value| !value.is_empty()) {
+        "NOT NULL"
+    } else {
+        "NULL"
+    };
+    format!("{} {} {}", name, infer_type(&values, driver), null)
+}
+
+/* The field name proposed for every col, from the headers if there are any.
+ * Repeated names get numbered, so each is unique */
+pub fn column_names(csv_data: &ImportedData) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for col in 0..csv_data.data.cols() {
+        let header = if csv_data.are_headers && !csv_data.data.is_empty() {
+            csv_data.data[0][col].data.as_str()
+        } else {
+            ""
+        };
+        let mut name = identifier(header);
+        if name.is_empty() {
+            name = format!("col_{}", col + 1);
+        }
+        let base_name = name.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{}_{}", base_name, suffix);
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/* Lowercase letters, digits and underscores, so the name never needs quoting */
+pub fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    identifier = identifier.trim_matches('_').to_owned();
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier = format!("_{}", identifier);
+    }
+    identifier.chars().take(64).collect()
+}
+
+/* The narrowest type every non-empty value fits, in the names the parser knows */
+fn infer_type(values: &[&str], driver: DBDriver) -> String {
+    let values: Vec<&str> = values
+        .iter()
+        .copied()
+        .filter(|value| !value.is_empty())
+        .collect();
+    if values.is_empty() {
+        return format!("varchar({})", MAX_VARCHAR_LENGTH);
+    }
+    /* leading zeros are part of codes like zip codes, a number would drop them */
+    let is_code = |value: &&str| {
+        let digits = value.trim_start_matches('-');
+        digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.")
+    };
+    if !values.iter().any(is_code) {
+        if values.iter().all(|value| value.parse::<i32>().is_ok()) {
+            return match driver {
+                DBDriver::Postgres => "integer".to_owned(),
+                DBDriver::MySql | DBDriver::Sqlite => "int".to_owned(),
+            };
+        }
+        if values.iter().all(|value| value.parse::<i64>().is_ok()) {
+            return "bigint".to_owned();
+        }
+        if let Some((whole, fraction)) = decimal_digits(&values) {
+            if whole + fraction <= MAX_DECIMAL_PRECISION {
+                return format!("decimal({},{})", (whole + fraction).max(1), fraction);
+            }
+        }
+    }
+    if values
+        .iter()
+        .all(|value| NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok())
+    {
+        return "date".to_owned();
+    }
+    if values
+        .iter()
+        .all(|value| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").is_ok())
+    {
+        return match driver {
+            DBDriver::Postgres => "timestamp".to_owned(),
+            DBDriver::MySql | DBDriver::Sqlite => "datetime".to_owned(),
+        };
+    }
+    let max_length = values
+        .iter()
+        .map(|value| value.chars().count())
+        .max()
+        .unwrap_or(1);
+    if max_length <= MAX_VARCHAR_LENGTH {
+        format!("varchar({})", max_length)
+    } else {
+        "text".to_owned()
+    }
+}
+
+/* Most digits before and after the point, None if a value isn't a plain decimal number */
+fn decimal_digits(values: &[&str]) -> Option<(usize, usize)> {
+    let mut max_whole = 0;
+    let mut max_fraction = 0;
+    for value in values {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_number = !whole.is_empty()
+            && whole.chars().all(|c| c.is_ascii_digit())
+            && fraction.chars().all(|c| c.is_ascii_digit());
+        if !is_number {
+            return None;
+        }
+        max_whole = max_whole.max(whole.trim_start_matches('0').len());
+        max_fraction = max_fraction.max(fraction.len());
+    }
+    Some((max_whole, max_fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::infer_type;
+    use crate::backend::db_connection::DBDriver;
+
+    #[test]
+    fn whole_numbers_get_the_narrowest_integer() {
+        assert_eq!(infer_type(&["1", "-20", ""], DBDriver::MySql), "int");
+        assert_eq!(infer_type(&["1", "-20"], DBDriver::Postgres), "integer");
+        assert_eq!(infer_type(&["1", "3000000000"], DBDriver::Sqlite), "bigint");
+    }
+
+    #[test]
+    fn decimals_fit_every_digit() {
+        assert_eq!(
+            infer_type(&["12.5", "3.25"], DBDriver::MySql),
+            "decimal(4,2)"
+        );
+        assert_eq!(
+            infer_type(&["0.5", "-0.25"], DBDriver::MySql),
+            "decimal(2,2)"
+        );
+        assert_eq!(infer_type(&["1", "2.5"], DBDriver::MySql), "decimal(2,1)");
+    }
+
+    #[test]
+    fn leading_zeros_stay_text() {
+        assert_eq!(
+            infer_type(&["01234", "98765"], DBDriver::MySql),
+            "varchar(5)"
+        );
+        assert_eq!(infer_type(&["0", "0.5"], DBDriver::MySql), "decimal(1,1)");
+    }
+
+    #[test]
+    fn dates_and_times_are_recognized() {
+        assert_eq!(infer_type(&["2023-04-01", ""], DBDriver::MySql), "date");
+        let times = ["2023-04-01 12:00:00"];
+        assert_eq!(infer_type(&times, DBDriver::MySql), "datetime");
+        assert_eq!(infer_type(&times, DBDriver::Postgres), "timestamp");
+    }
+
+    #[test]
+    fn anything_else_is_text_as_long_as_the_longest_value() {
+        assert_eq!(infer_type(&["1", "abc"], DBDriver::MySql), "varchar(3)");
+        assert_eq!(infer_type(&["žluťoučký"], DBDriver::MySql), "varchar(9)");
+        assert_eq!(infer_type(&[&"x".repeat(300)], DBDriver::MySql), "text");
+        assert_eq!(infer_type(&["", ""], DBDriver::MySql), "varchar(255)");
+    }
+}
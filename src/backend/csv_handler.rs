@@ -1,5 +1,7 @@
 use super::database_handler::FieldDescription;
+use super::db_connection::TextResultSet;
 
+use std::io::Write;
 use std::{error::Error, sync::Arc};
 
 #[derive(Clone)]
@@ -59,6 +61,154 @@ impl ImportedData {
         }
         Ok(())
     }
+    /* Writes the grid as it is now, header row included */
+    pub fn save_csv(&self, path: &str, dialect: &CsvDialect) -> Result<(), Box<dyn Error + Send>> {
+        let mut file = match std::fs::File::create(path) {
+            Ok(file) => file,
+            Err(e) => return Err(Box::new(e)),
+        };
+        if dialect.with_bom {
+            if let Err(e) = file.write_all(b"\xEF\xBB\xBF") {
+                return Err(Box::new(e));
+            }
+        }
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(dialect.delimiter)
+            .quote_style(dialect.quote_style.into())
+            .terminator(dialect.line_ending.into())
+            /* rows can be uneven if the grid came from a loosely formatted file */
+            .flexible(true)
+            .from_writer(file);
+
+        for row in 0..self.data.rows() {
+            let record: Vec<&str> = self.data[row]
+                .iter()
+                .map(|cell| cell.data.as_str())
+                .collect();
+            if let Err(e) = wtr.write_record(record) {
+                return Err(Box::new(e));
+            }
+        }
+        match wtr.flush() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+    /* Loads rows queried from a table, with the column names as headers. NULLs become empty cells */
+    pub fn load_result_set(&mut self, result_set: TextResultSet) {
+        self.data = grid::Grid::new(0, 0);
+        self.data.push_row(
+            result_set
+                .columns
+                .into_iter()
+                .map(|column| DataEntry {
+                    data: column,
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        for row in result_set.rows {
+            self.data.push_row(
+                row.into_iter()
+                    .map(|value| DataEntry {
+                        data: value.unwrap_or_default(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            );
+        }
+        self.path = String::new();
+        self.are_headers = true;
+        self.parsed_cols.clear();
+        self.is_parsed = false;
+    }
+}
+
+/* How the grid gets written back to disk */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvDialect {
+    pub delimiter: u8,
+    pub quote_style: QuoteStyle,
+    pub line_ending: LineEnding,
+    /* Excel only detects UTF-8 with a BOM */
+    pub with_bom: bool,
+}
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: b',',
+            quote_style: QuoteStyle::Necessary,
+            line_ending: LineEnding::Lf,
+            with_bom: false,
+        }
+    }
+}
+impl CsvDialect {
+    pub const DELIMITERS: [(u8, &'static str); 4] = [
+        (b',', "Comma"),
+        (b';', "Semicolon"),
+        (b'\t', "Tab"),
+        (b'|', "Pipe"),
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    Necessary,
+    Always,
+    NonNumeric,
+    Never,
+}
+impl QuoteStyle {
+    pub const ALL: [QuoteStyle; 4] = [
+        QuoteStyle::Necessary,
+        QuoteStyle::Always,
+        QuoteStyle::NonNumeric,
+        QuoteStyle::Never,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuoteStyle::Necessary => "When necessary",
+            QuoteStyle::Always => "Always",
+            QuoteStyle::NonNumeric => "Non-numeric",
+            QuoteStyle::Never => "Never",
+        }
+    }
+}
+impl From<QuoteStyle> for csv::QuoteStyle {
+    fn from(quote_style: QuoteStyle) -> Self {
+        match quote_style {
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+            QuoteStyle::NonNumeric => csv::QuoteStyle::NonNumeric,
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::CrLf];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF (Unix)",
+            LineEnding::CrLf => "CRLF (Windows)",
+        }
+    }
+}
+impl From<LineEnding> for csv::Terminator {
+    fn from(line_ending: LineEnding) -> Self {
+        match line_ending {
+            LineEnding::Lf => csv::Terminator::Any(b'\n'),
+            LineEnding::CrLf => csv::Terminator::CRLF,
+        }
+    }
 }
 
 impl Default for ImportedData {
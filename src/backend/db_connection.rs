@@ -0,0 +1,283 @@
+pub mod mysql;
+pub mod pool;
+pub mod postgres;
+pub mod script;
+pub mod sqlite;
+
+use super::database_handler::{FieldDescription, ForeignKey};
+use super::sql_value::SqlValue;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::any::AnyQueryResult;
+use sqlx::{Column, ColumnIndex, Decode, Row};
+
+/* Everything the rest of CSQL needs from a live database, implemented once per driver */
+#[async_trait]
+pub trait DBConnection: Send {
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error>;
+    /* Runs a prepared statement, binding `values` to its placeholders in order */
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error>;
+    /* Runs a prepared statement and tells whether it returned anything */
+    async fn fetch_has_rows(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error>;
+    /* Runs a plain query, reading every value back as text */
+    async fn fetch_rows_as_text(&mut self, query: &str) -> Result<TextResultSet, sqlx::Error>;
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error>;
+    async fn describe_table(
+        &mut self,
+        table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error>;
+    /* Columns of every UNIQUE and PRIMARY index on the table */
+    async fn unique_key_sets(&mut self, table_name: &str) -> Result<Vec<Vec<String>>, sqlx::Error>;
+    /* Biggest packet the server accepts in bytes, None if there's no practical limit */
+    async fn max_packet_size(&mut self) -> Result<Option<usize>, sqlx::Error> {
+        Ok(None)
+    }
+    /* Most placeholders a single prepared statement can hold */
+    fn max_bind_values(&self) -> usize {
+        u16::MAX as usize
+    }
+    /* Placeholder for the n-th (1 based) bound value, going into a field like `description` */
+    fn placeholder(&self, _index: usize, _description: &FieldDescription) -> String {
+        "?".to_owned()
+    }
+    /* Appended to an INSERT, so rows colliding on `key_fields` update `update_fields` instead */
+    fn upsert_clause(&self, key_fields: &[String], update_fields: &[&str]) -> String {
+        on_conflict_clause(key_fields, update_fields)
+    }
+    /* Keys the database generated for the `row_count` rows of an INSERT in row order,
+     * None if it can't tell */
+    async fn inserted_ids(
+        &mut self,
+        _result: &AnyQueryResult,
+        _row_count: usize,
+    ) -> Result<Option<Vec<i64>>, sqlx::Error> {
+        Ok(None)
+    }
+    /* Runs an INSERT of `row_count` rows like execute_with_values, also reading back the keys
+     * `key_field` got. By default they're worked out by inserted_ids() once it's in */
+    async fn execute_returning_keys(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+        row_count: usize,
+        _key_field: &str,
+    ) -> Result<(AnyQueryResult, Option<Vec<String>>), sqlx::Error> {
+        let result = self.execute_with_values(query, values).await?;
+        /* the rows are in either way, not knowing their keys only stops the journaling */
+        let ids = self
+            .inserted_ids(&result, row_count)
+            .await
+            .unwrap_or_else(|e| {
+                println!("db - failed to tell the inserted keys, {}", e);
+                None
+            });
+        let keys = ids.map(|ids| ids.iter().map(|id| id.to_string()).collect());
+        Ok((result, keys))
+    }
+    /* Key `key_field` got generated for the single row `result` inserted into `table_name` */
+    async fn last_insert_id(
+        &mut self,
+        result: &AnyQueryResult,
+        _table_name: &str,
+        _key_field: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let ids = self.inserted_ids(result, 1).await?;
+        Ok(ids.and_then(|ids| ids.first().copied()))
+    }
+    /* Stands in for a value so the field takes its default */
+    fn default_value(&self, _description: &FieldDescription) -> String {
+        "DEFAULT".to_owned()
+    }
+    /* Statements run in order by Table::start_transaction */
+    fn begin_transaction_queries(&self) -> Vec<&'static str> {
+        vec!["BEGIN"]
+    }
+    /* Warnings the last statement left behind, for servers that accept data with a warning */
+    async fn fetch_warnings(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        Ok(Vec::new())
+    }
+}
+
+/* The upsert of postgres and sqlite, both name the unique key it collides on */
+fn on_conflict_clause(key_fields: &[String], update_fields: &[&str]) -> String {
+    if update_fields.is_empty() {
+        return format!("ON CONFLICT ({}) DO NOTHING", key_fields.join(", "));
+    }
+    format!(
+        "ON CONFLICT ({}) DO UPDATE SET {}",
+        key_fields.join(", "),
+        update_fields
+            .iter()
+            .map(|field| format!("{0} = excluded.{0}", field))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/* Rows of a query with every value as text, NULL as None */
+#[derive(Default, Clone, Debug)]
+pub struct TextResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+impl TextResultSet {
+    pub fn column_index(&self, column_name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == column_name)
+    }
+}
+/* mysql and postgres send unprepared results as text, so any column decodes into a String */
+fn rows_to_text<R: Row>(qr_rows: Vec<R>) -> Result<TextResultSet, sqlx::Error>
+where
+    usize: ColumnIndex<R>,
+    for<'r> Option<String>: Decode<'r, R::Database>,
+{
+    let mut result_set = TextResultSet::default();
+    if let Some(first_row) = qr_rows.first() {
+        result_set.columns = first_row
+            .columns()
+            .iter()
+            .map(|column| column.name().to_owned())
+            .collect();
+    }
+    for row in qr_rows.iter() {
+        let mut values = Vec::with_capacity(row.len());
+        for i in 0..row.len() {
+            values.push(row.try_get_unchecked::<Option<String>, usize>(i)?);
+        }
+        result_set.rows.push(values);
+    }
+    Ok(result_set)
+}
+
+/* Hands the (column, referenced table, referenced column) and (index name, column)
+ * rows every driver queries separately out to the fields they belong to */
+fn attach_keys(
+    fields: &mut [FieldDescription],
+    qr_foreign_keys: Vec<(String, String, String)>,
+    qr_index_columns: Vec<(String, String)>,
+) {
+    for (column_name, referenced_table, referenced_column) in qr_foreign_keys {
+        if let Some(field) = fields.iter_mut().find(|field| field.field == column_name) {
+            field.foreign_key = Some(ForeignKey {
+                table: referenced_table,
+                field: referenced_column,
+            });
+        }
+    }
+    for (index_name, column_name) in qr_index_columns {
+        if let Some(field) = fields.iter_mut().find(|field| field.field == column_name) {
+            if !field.indexes.contains(&index_name) {
+                field.indexes.push(index_name);
+            }
+        }
+    }
+}
+
+/* "enum('a','it''s')" -> ["a", "it's"] */
+fn parse_enum_values(column_type: &str) -> Vec<String> {
+    let (start, end) = match (column_type.find('('), column_type.rfind(')')) {
+        (Some(start), Some(end)) if start < end => (start + 1, end),
+        _ => return Vec::new(),
+    };
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut is_quoted = false;
+    let mut chars = column_type[start..end].chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if is_quoted && chars.peek() == Some(&'\'') => {
+                value.push('\'');
+                chars.next();
+            }
+            '\'' if is_quoted => {
+                values.push(std::mem::take(&mut value));
+                is_quoted = false;
+            }
+            '\'' => is_quoted = true,
+            c if is_quoted => value.push(c),
+            _ => {}
+        }
+    }
+    values
+}
+
+/* (index name, column name) rows ordered by index and position -> column list per index */
+fn group_index_columns(qr_index_columns: Vec<(String, String)>) -> Vec<Vec<String>> {
+    let mut key_sets: Vec<Vec<String>> = Vec::new();
+    let mut last_index_name: Option<String> = None;
+    for (index_name, column_name) in qr_index_columns {
+        if last_index_name.as_ref() == Some(&index_name) {
+            key_sets.last_mut().unwrap().push(column_name);
+        } else {
+            key_sets.push(vec![column_name]);
+            last_index_name = Some(index_name);
+        }
+    }
+    key_sets
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DBDriver {
+    #[default]
+    MySql,
+    Postgres,
+    Sqlite,
+}
+
+impl DBDriver {
+    pub const ALL: [DBDriver; 3] = [DBDriver::MySql, DBDriver::Postgres, DBDriver::Sqlite];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DBDriver::MySql => "MySQL",
+            DBDriver::Postgres => "PostgreSQL",
+            DBDriver::Sqlite => "SQLite",
+        }
+    }
+    pub fn default_port(&self) -> &'static str {
+        match self {
+            DBDriver::MySql => "3306",
+            DBDriver::Postgres => "5432",
+            DBDriver::Sqlite => "",
+        }
+    }
+    pub fn is_file_based(&self) -> bool {
+        *self == DBDriver::Sqlite
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_enum_values;
+
+    #[test]
+    fn enum_values_are_unquoted() {
+        assert_eq!(
+            parse_enum_values("enum('a','it''s','')"),
+            vec!["a".to_owned(), "it's".to_owned(), String::new()]
+        );
+    }
+
+    #[test]
+    fn commas_and_parens_inside_values_stay() {
+        assert_eq!(
+            parse_enum_values("set('x,y','(z)')"),
+            vec!["x,y".to_owned(), "(z)".to_owned()]
+        );
+    }
+
+    #[test]
+    fn types_without_values_have_none() {
+        assert!(parse_enum_values("enum()").is_empty());
+        assert!(parse_enum_values("int(11)").is_empty());
+        assert!(parse_enum_values("text").is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use super::{
+    attach_keys, group_index_columns, parse_enum_values, rows_to_text, DBConnection, TextResultSet,
+};
+use crate::backend::database_handler::FieldDescription;
+use crate::backend::sql_value::SqlValue;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::any::AnyQueryResult;
+use sqlx::mysql::MySqlArguments;
+use sqlx::query::Query;
+use sqlx::{Executor, FromRow, MySql, MySqlConnection};
+
+#[async_trait]
+impl DBConnection for MySqlConnection {
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error> {
+        Executor::execute(self, query)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .execute(self)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn fetch_has_rows(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .fetch_optional(self)
+            .await
+            .map(|row| row.is_some())
+    }
+    async fn fetch_rows_as_text(&mut self, query: &str) -> Result<TextResultSet, sqlx::Error> {
+        rows_to_text(Executor::fetch_all(self, query).await?)
+    }
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        let qr_tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT TABLE_NAME FROM information_schema.TABLES \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE' \
+             ORDER BY TABLE_NAME",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(qr_tables.into_iter().map(|table| table.0).collect())
+    }
+    async fn describe_table(
+        &mut self,
+        table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error> {
+        /* the lengths are bigint on 8.0 but int on 5.7, so cast them to one type */
+        let qr_columns: Vec<QRColumn> = sqlx::query_as(
+            "SELECT COLUMN_NAME AS column_name, COLUMN_TYPE AS column_type, \
+                DATA_TYPE AS data_type, IS_NULLABLE AS is_nullable, COLUMN_KEY AS column_key, \
+                COLUMN_DEFAULT AS column_default, EXTRA AS extra, \
+                CAST(CHARACTER_MAXIMUM_LENGTH AS UNSIGNED) AS max_length, \
+                CAST(NUMERIC_PRECISION AS UNSIGNED) AS numeric_precision, \
+                CAST(NUMERIC_SCALE AS UNSIGNED) AS numeric_scale, \
+                CHARACTER_SET_NAME AS charset, COLUMN_COMMENT AS comment \
+             FROM information_schema.COLUMNS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+             ORDER BY ORDINAL_POSITION",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let qr_foreign_keys: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT k.COLUMN_NAME, k.REFERENCED_TABLE_NAME, k.REFERENCED_COLUMN_NAME \
+             FROM information_schema.KEY_COLUMN_USAGE k \
+             JOIN information_schema.TABLE_CONSTRAINTS c \
+                ON c.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA \
+                AND c.CONSTRAINT_NAME = k.CONSTRAINT_NAME \
+                AND c.TABLE_NAME = k.TABLE_NAME \
+             WHERE k.TABLE_SCHEMA = DATABASE() AND k.TABLE_NAME = ? \
+                AND c.CONSTRAINT_TYPE = 'FOREIGN KEY'",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let qr_index_columns: Vec<(String, String)> = sqlx::query_as(
+            "SELECT INDEX_NAME, COLUMN_NAME FROM information_schema.STATISTICS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? \
+             ORDER BY INDEX_NAME, SEQ_IN_INDEX",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let mut fields: Vec<FieldDescription> =
+            qr_columns.into_iter().map(|column| column.into()).collect();
+        attach_keys(&mut fields, qr_foreign_keys, qr_index_columns);
+        Ok(fields)
+    }
+    async fn unique_key_sets(&mut self, table_name: &str) -> Result<Vec<Vec<String>>, sqlx::Error> {
+        let qr_index_columns: Vec<(String, String)> = sqlx::query_as(
+            "SELECT INDEX_NAME, COLUMN_NAME FROM information_schema.STATISTICS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND NON_UNIQUE = 0 \
+             ORDER BY INDEX_NAME, SEQ_IN_INDEX",
+        )
+        .bind(table_name)
+        .fetch_all(self)
+        .await?;
+        Ok(group_index_columns(qr_index_columns))
+    }
+    async fn max_packet_size(&mut self) -> Result<Option<usize>, sqlx::Error> {
+        let max_allowed_packet: (u64,) =
+            sqlx::query_as("SELECT CAST(@@max_allowed_packet AS UNSIGNED)")
+                .fetch_one(self)
+                .await?;
+        Ok(Some(max_allowed_packet.0 as usize))
+    }
+    fn upsert_clause(&self, key_fields: &[String], update_fields: &[&str]) -> String {
+        on_duplicate_key_clause(key_fields, update_fields)
+    }
+    /* LAST_INSERT_ID() is the first id of a multi-row insert, the rest follow it
+     * auto_increment_increment apart (more than 1 under galera or group replication),
+     * as long as innodb_autoinc_lock_mode doesn't interleave them */
+    async fn inserted_ids(
+        &mut self,
+        result: &AnyQueryResult,
+        row_count: usize,
+    ) -> Result<Option<Vec<i64>>, sqlx::Error> {
+        let first_id = match result.last_insert_id() {
+            Some(first_id) if first_id > 0 => first_id,
+            _ => return Ok(None),
+        };
+        let increment: (u64,) =
+            sqlx::query_as("SELECT CAST(@@auto_increment_increment AS UNSIGNED)")
+                .fetch_one(self)
+                .await?;
+        let increment = increment.0.max(1) as i64;
+        Ok(Some(
+            (0..row_count as i64)
+                .map(|row_i| first_id + row_i * increment)
+                .collect(),
+        ))
+    }
+    /* a single row needs no increment */
+    async fn last_insert_id(
+        &mut self,
+        result: &AnyQueryResult,
+        _table_name: &str,
+        _key_field: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        Ok(result.last_insert_id().filter(|id| *id > 0))
+    }
+    /* truncated or coerced values only show up here unless sql_mode is strict */
+    async fn fetch_warnings(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        let warnings = self.fetch_rows_as_text("SHOW WARNINGS").await?;
+        let (level, code, message) = (
+            warnings.column_index("Level"),
+            warnings.column_index("Code"),
+            warnings.column_index("Message"),
+        );
+        let text = |row: &Vec<Option<String>>, index: Option<usize>| {
+            index
+                .and_then(|index| row[index].clone())
+                .unwrap_or_default()
+        };
+        Ok(warnings
+            .rows
+            .iter()
+            .map(|row| {
+                format!(
+                    "{} {}: {}",
+                    text(row, level),
+                    text(row, code),
+                    text(row, message)
+                )
+            })
+            .collect())
+    }
+}
+
+/* mysql picks the colliding unique key by itself */
+pub(super) fn on_duplicate_key_clause(key_fields: &[String], update_fields: &[&str]) -> String {
+    if update_fields.is_empty() {
+        return format!("ON DUPLICATE KEY UPDATE {0} = {0}", key_fields[0]);
+    }
+    format!(
+        "ON DUPLICATE KEY UPDATE {}",
+        update_fields
+            .iter()
+            .map(|field| format!("{0} = VALUES({0})", field))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+#[derive(FromRow)]
+struct QRColumn {
+    column_name: String,
+    column_type: String,
+    data_type: String,
+    is_nullable: String,
+    column_key: String,
+    column_default: Option<String>,
+    extra: String,
+    max_length: Option<u64>,
+    numeric_precision: Option<u64>,
+    numeric_scale: Option<u64>,
+    charset: Option<String>,
+    comment: String,
+}
+
+impl From<QRColumn> for FieldDescription {
+    fn from(column: QRColumn) -> Self {
+        let enum_values = match column.data_type.as_str() {
+            "enum" | "set" => parse_enum_values(&column.column_type),
+            _ => Vec::new(),
+        };
+        FieldDescription {
+            field: column.column_name,
+            is_unsigned: column.column_type.contains("unsigned"),
+            field_type: column.column_type,
+            null: column.is_nullable,
+            key: column.column_key,
+            default: column.column_default,
+            extra: column.extra,
+            max_length: column.max_length,
+            numeric_precision: column.numeric_precision,
+            numeric_scale: column.numeric_scale,
+            charset: column.charset,
+            enum_values,
+            comment: column.comment,
+            ..Default::default()
+        }
+    }
+}
+
+fn bind_values<'q>(
+    mut sql_query: Query<'q, MySql, MySqlArguments>,
+    values: Vec<SqlValue>,
+) -> Query<'q, MySql, MySqlArguments> {
+    for value in values {
+        sql_query = match value {
+            SqlValue::Null => sql_query.bind(None::<String>),
+            SqlValue::Int(val) => sql_query.bind(val),
+            SqlValue::Float(val) => sql_query.bind(val),
+            SqlValue::Decimal(val) => sql_query.bind(val),
+            SqlValue::Bool(val) => sql_query.bind(val),
+            SqlValue::Text(val) => sql_query.bind(val),
+            SqlValue::Date(val) => sql_query.bind(val),
+            SqlValue::DateTime(val) => sql_query.bind(val),
+            /* sqlx sets the session's time_zone to +00:00 */
+            SqlValue::DateTimeTz(val) => sql_query.bind(val.with_timezone(&Utc)),
+        };
+    }
+    sql_query
+}
@@ -0,0 +1,111 @@
+use super::{DBConnection, TextResultSet};
+use crate::backend::database_handler::FieldDescription;
+use crate::backend::sql_value::SqlValue;
+use async_trait::async_trait;
+use sqlx::any::AnyQueryResult;
+use sqlx::pool::PoolConnection;
+use sqlx::{Database, Pool};
+
+/* Hands out connections of whichever driver the pool was opened with */
+#[async_trait]
+pub trait DBPool: Send + Sync {
+    async fn acquire(&self) -> Result<Box<dyn DBConnection>, sqlx::Error>;
+}
+#[async_trait]
+impl<DB> DBPool for Pool<DB>
+where
+    DB: Database,
+    DB::Connection: DBConnection,
+{
+    async fn acquire(&self) -> Result<Box<dyn DBConnection>, sqlx::Error> {
+        Ok(Box::new(Pool::acquire(self).await?))
+    }
+}
+
+/* A pooled connection behaves like the one it wraps, and goes back to the pool once dropped */
+#[async_trait]
+impl<DB> DBConnection for PoolConnection<DB>
+where
+    DB: Database,
+    DB::Connection: DBConnection,
+{
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error> {
+        DBConnection::execute(&mut **self, query).await
+    }
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error> {
+        (**self).execute_with_values(query, values).await
+    }
+    async fn fetch_has_rows(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error> {
+        (**self).fetch_has_rows(query, values).await
+    }
+    async fn fetch_rows_as_text(&mut self, query: &str) -> Result<TextResultSet, sqlx::Error> {
+        (**self).fetch_rows_as_text(query).await
+    }
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        (**self).query_for_tables().await
+    }
+    async fn describe_table(
+        &mut self,
+        table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error> {
+        (**self).describe_table(table_name).await
+    }
+    async fn unique_key_sets(&mut self, table_name: &str) -> Result<Vec<Vec<String>>, sqlx::Error> {
+        (**self).unique_key_sets(table_name).await
+    }
+    async fn max_packet_size(&mut self) -> Result<Option<usize>, sqlx::Error> {
+        (**self).max_packet_size().await
+    }
+    fn max_bind_values(&self) -> usize {
+        (**self).max_bind_values()
+    }
+    fn placeholder(&self, index: usize, description: &FieldDescription) -> String {
+        (**self).placeholder(index, description)
+    }
+    fn upsert_clause(&self, key_fields: &[String], update_fields: &[&str]) -> String {
+        (**self).upsert_clause(key_fields, update_fields)
+    }
+    async fn inserted_ids(
+        &mut self,
+        result: &AnyQueryResult,
+        row_count: usize,
+    ) -> Result<Option<Vec<i64>>, sqlx::Error> {
+        (**self).inserted_ids(result, row_count).await
+    }
+    async fn execute_returning_keys(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+        row_count: usize,
+        key_field: &str,
+    ) -> Result<(AnyQueryResult, Option<Vec<String>>), sqlx::Error> {
+        (**self)
+            .execute_returning_keys(query, values, row_count, key_field)
+            .await
+    }
+    async fn last_insert_id(
+        &mut self,
+        result: &AnyQueryResult,
+        table_name: &str,
+        key_field: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        (**self).last_insert_id(result, table_name, key_field).await
+    }
+    fn default_value(&self, description: &FieldDescription) -> String {
+        (**self).default_value(description)
+    }
+    fn begin_transaction_queries(&self) -> Vec<&'static str> {
+        (**self).begin_transaction_queries()
+    }
+    async fn fetch_warnings(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        (**self).fetch_warnings().await
+    }
+}
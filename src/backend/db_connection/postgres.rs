@@ -0,0 +1,287 @@
+use super::{attach_keys, group_index_columns, rows_to_text, DBConnection, TextResultSet};
+use crate::backend::database_handler::FieldDescription;
+use crate::backend::sql_value::SqlValue;
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use sqlx::any::AnyQueryResult;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{Either, Executor, FromRow, PgConnection, Postgres, Row};
+
+#[async_trait]
+impl DBConnection for PgConnection {
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error> {
+        Executor::execute(self, query)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .execute(self)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn unique_key_sets(&mut self, table_name: &str) -> Result<Vec<Vec<String>>, sqlx::Error> {
+        let qr_index_columns: Vec<(String, String)> = sqlx::query_as(
+            "SELECT i.relname::text, a.attname::text FROM pg_index x \
+             JOIN pg_class t ON t.oid = x.indrelid \
+             JOIN pg_class i ON i.oid = x.indexrelid \
+             JOIN pg_namespace n ON n.oid = t.relnamespace \
+             CROSS JOIN LATERAL unnest(x.indkey) WITH ORDINALITY AS k(attnum, ord) \
+             JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum \
+             WHERE x.indisunique AND n.nspname = current_schema() AND t.relname = $1 \
+             ORDER BY i.relname, k.ord",
+        )
+        .bind(table_name)
+        .fetch_all(self)
+        .await?;
+        Ok(group_index_columns(qr_index_columns))
+    }
+    /* text parameters don't cast to booleans, enums, uuids or json on their own,
+     * so the value is cast to the field's type explicitly */
+    fn placeholder(&self, index: usize, description: &FieldDescription) -> String {
+        match &description.native_type {
+            Some(native_type) => format!("${}::{}", index, native_type),
+            None => format!("${}", index),
+        }
+    }
+    async fn fetch_has_rows(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .fetch_optional(self)
+            .await
+            .map(|row| row.is_some())
+    }
+    async fn fetch_rows_as_text(&mut self, query: &str) -> Result<TextResultSet, sqlx::Error> {
+        rows_to_text(Executor::fetch_all(self, query).await?)
+    }
+    /* postgres results carry no insert ids, RETURNING hands back the keys of exactly
+     * the rows that went in, whatever other sessions took from the sequence meanwhile */
+    async fn execute_returning_keys(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+        _row_count: usize,
+        key_field: &str,
+    ) -> Result<(AnyQueryResult, Option<Vec<String>>), sqlx::Error> {
+        let query = format!("{} RETURNING {}::text", query, key_field);
+        let mut result = AnyQueryResult::default();
+        let mut keys: Vec<Option<String>> = Vec::new();
+        let mut results = bind_values(sqlx::query(&query), values).fetch_many(&mut *self);
+        while let Some(item) = results.try_next().await? {
+            match item {
+                Either::Left(done) => result.extend(std::iter::once(AnyQueryResult::from(done))),
+                Either::Right(row) => keys.push(row.try_get(0)?),
+            }
+        }
+        /* a NULL key can't be told apart from the rest */
+        Ok((result, keys.into_iter().collect()))
+    }
+    /* postgres results carry no insert id, but the key's sequence remembers what it handed out.
+     * Keys without a sequence come back NULL instead of failing the transaction */
+    async fn last_insert_id(
+        &mut self,
+        _result: &AnyQueryResult,
+        table_name: &str,
+        key_field: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let id: (Option<i64>,) = sqlx::query_as("SELECT currval(pg_get_serial_sequence($1, $2))")
+            .bind(table_name)
+            .bind(key_field)
+            .fetch_one(self)
+            .await?;
+        Ok(id.0)
+    }
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        let qr_tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT table_name::text FROM information_schema.tables \
+             WHERE table_schema = current_schema() AND table_type = 'BASE TABLE' \
+             ORDER BY table_name",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(qr_tables.into_iter().map(|table| table.0).collect())
+    }
+    async fn describe_table(
+        &mut self,
+        table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error> {
+        let qr_columns: Vec<QRColumn> = sqlx::query_as(
+            "SELECT c.column_name::text, c.data_type::text, c.udt_schema::text, c.udt_name::text, \
+                c.character_maximum_length::int4, c.numeric_precision::int4, \
+                c.numeric_scale::int4, c.is_nullable::text, c.column_default::text, \
+                c.is_identity::text, c.character_set_name::text, \
+                col_description(a.attrelid, a.attnum) AS comment, \
+                (SELECT array_agg(e.enumlabel::text ORDER BY e.enumsortorder) \
+                    FROM pg_enum e JOIN pg_type t ON t.oid = e.enumtypid \
+                    WHERE t.typname = c.udt_name) AS enum_values, \
+                COALESCE((SELECT CASE tc.constraint_type \
+                        WHEN 'PRIMARY KEY' THEN 'PRI' WHEN 'UNIQUE' THEN 'UNI' END \
+                    FROM information_schema.table_constraints tc \
+                    JOIN information_schema.key_column_usage kcu \
+                        ON tc.constraint_name = kcu.constraint_name \
+                        AND tc.table_schema = kcu.table_schema \
+                    WHERE tc.table_schema = c.table_schema \
+                        AND tc.table_name = c.table_name \
+                        AND kcu.column_name = c.column_name \
+                        AND tc.constraint_type IN ('PRIMARY KEY', 'UNIQUE') \
+                    ORDER BY tc.constraint_type LIMIT 1), '') AS column_key \
+             FROM information_schema.columns c \
+             JOIN pg_attribute a \
+                ON a.attrelid = format('%I.%I', c.table_schema, c.table_name)::regclass \
+                AND a.attname = c.column_name \
+             WHERE c.table_schema = current_schema() AND c.table_name = $1 \
+             ORDER BY c.ordinal_position",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let qr_foreign_keys: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT a.attname::text, rt.relname::text, ra.attname::text FROM pg_constraint x \
+             JOIN pg_class t ON t.oid = x.conrelid \
+             JOIN pg_namespace n ON n.oid = t.relnamespace \
+             JOIN pg_class rt ON rt.oid = x.confrelid \
+             CROSS JOIN LATERAL unnest(x.conkey, x.confkey) AS k(attnum, refattnum) \
+             JOIN pg_attribute a ON a.attrelid = x.conrelid AND a.attnum = k.attnum \
+             JOIN pg_attribute ra ON ra.attrelid = x.confrelid AND ra.attnum = k.refattnum \
+             WHERE x.contype = 'f' AND n.nspname = current_schema() AND t.relname = $1",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let qr_index_columns: Vec<(String, String)> = sqlx::query_as(
+            "SELECT i.relname::text, a.attname::text FROM pg_index x \
+             JOIN pg_class t ON t.oid = x.indrelid \
+             JOIN pg_class i ON i.oid = x.indexrelid \
+             JOIN pg_namespace n ON n.oid = t.relnamespace \
+             CROSS JOIN LATERAL unnest(x.indkey) WITH ORDINALITY AS k(attnum, ord) \
+             JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum \
+             WHERE n.nspname = current_schema() AND t.relname = $1 \
+             ORDER BY i.relname, k.ord",
+        )
+        .bind(table_name)
+        .fetch_all(&mut *self)
+        .await?;
+
+        let mut fields: Vec<FieldDescription> =
+            qr_columns.into_iter().map(|column| column.into()).collect();
+        attach_keys(&mut fields, qr_foreign_keys, qr_index_columns);
+        Ok(fields)
+    }
+}
+
+#[derive(FromRow)]
+struct QRColumn {
+    column_name: String,
+    data_type: String,
+    udt_schema: String,
+    udt_name: String,
+    character_maximum_length: Option<i32>,
+    numeric_precision: Option<i32>,
+    numeric_scale: Option<i32>,
+    is_nullable: String,
+    column_default: Option<String>,
+    is_identity: Option<String>,
+    character_set_name: Option<String>,
+    comment: Option<String>,
+    enum_values: Option<Vec<String>>,
+    column_key: String,
+}
+
+/* Translates postgres types to the mysql-like names the parser understands */
+impl From<QRColumn> for FieldDescription {
+    fn from(column: QRColumn) -> Self {
+        let field_type = match column.data_type.as_str() {
+            "character varying" => match column.character_maximum_length {
+                Some(len) => format!("varchar({})", len),
+                None => "text".to_owned(),
+            },
+            "character" => format!("char({})", column.character_maximum_length.unwrap_or(1)),
+            "integer" => "int".to_owned(),
+            "numeric" => match (column.numeric_precision, column.numeric_scale) {
+                (Some(precision), Some(scale)) => format!("decimal({},{})", precision, scale),
+                _ => "decimal".to_owned(),
+            },
+            "double precision" => "double".to_owned(),
+            "timestamp without time zone" => "datetime".to_owned(),
+            "timestamp with time zone" => "timestamp".to_owned(),
+            "USER-DEFINED" => match &column.enum_values {
+                Some(values) => format!(
+                    "enum({})",
+                    values
+                        .iter()
+                        .map(|value| format!("'{}'", value.replace('\'', "''")))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+                None => column.udt_name.clone(),
+            },
+            other => other.to_owned(),
+        };
+        let is_auto_increment = column.is_identity.as_deref() == Some("YES")
+            || column
+                .column_default
+                .as_ref()
+                .is_some_and(|default| default.starts_with("nextval("));
+
+        /* user defined types live in the table's schema, which may not be on the search path */
+        let native_type = if column.udt_schema == "pg_catalog" {
+            column.udt_name.clone()
+        } else {
+            format!(
+                "\"{}\".\"{}\"",
+                column.udt_schema.replace('"', "\"\""),
+                column.udt_name.replace('"', "\"\"")
+            )
+        };
+        FieldDescription {
+            field: column.column_name,
+            field_type,
+            native_type: Some(native_type),
+            null: column.is_nullable,
+            key: column.column_key,
+            default: column.column_default,
+            extra: if is_auto_increment {
+                "auto_increment".to_owned()
+            } else {
+                String::new()
+            },
+            max_length: column.character_maximum_length.map(|len| len as u64),
+            numeric_precision: column.numeric_precision.map(|precision| precision as u64),
+            numeric_scale: column.numeric_scale.map(|scale| scale as u64),
+            charset: column.character_set_name,
+            enum_values: column.enum_values.unwrap_or_default(),
+            comment: column.comment.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+fn bind_values<'q>(
+    mut sql_query: Query<'q, Postgres, PgArguments>,
+    values: Vec<SqlValue>,
+) -> Query<'q, Postgres, PgArguments> {
+    for value in values {
+        sql_query = match value {
+            SqlValue::Null => sql_query.bind(None::<String>),
+            SqlValue::Int(val) => sql_query.bind(val),
+            SqlValue::Float(val) => sql_query.bind(val),
+            SqlValue::Decimal(val) => sql_query.bind(val),
+            SqlValue::Bool(val) => sql_query.bind(val),
+            SqlValue::Text(val) => sql_query.bind(val),
+            SqlValue::Date(val) => sql_query.bind(val),
+            SqlValue::DateTime(val) => sql_query.bind(val),
+            SqlValue::DateTimeTz(val) => sql_query.bind(val),
+        };
+    }
+    sql_query
+}
@@ -0,0 +1,127 @@
+use super::mysql::on_duplicate_key_clause;
+use super::sqlite::default_expression;
+use super::{on_conflict_clause, DBConnection, DBDriver, TextResultSet};
+use crate::backend::database_handler::FieldDescription;
+use crate::backend::sql_value::SqlValue;
+use async_trait::async_trait;
+use sqlx::any::AnyQueryResult;
+
+/* Writes the statements down instead of running them, in the SQL of `driver`.
+ * Bound values are inlined as literals, so the script runs on its own */
+pub struct ScriptConnection {
+    driver: DBDriver,
+    statements: Vec<String>,
+}
+impl ScriptConnection {
+    pub fn new(driver: DBDriver) -> ScriptConnection {
+        ScriptConnection {
+            driver,
+            statements: Vec::new(),
+        }
+    }
+    pub fn comment(&mut self, comment: &str) {
+        self.statements.push(format!("-- {}", comment));
+    }
+    pub fn statement_count(&self) -> usize {
+        self.statements
+            .iter()
+            .filter(|statement| !statement.starts_with("--"))
+            .count()
+    }
+    pub fn script(&self) -> String {
+        let mut script = String::new();
+        for statement in self.statements.iter() {
+            script.push_str(statement);
+            if !statement.starts_with("--") {
+                script.push(';');
+            }
+            script.push('\n');
+        }
+        script
+    }
+}
+
+/* Marks the n-th value in the statement text, NUL never shows up in the SQL around it */
+fn value_marker(index: usize) -> String {
+    format!("\0{}\0", index)
+}
+
+fn not_queryable() -> sqlx::Error {
+    sqlx::Error::Protocol("a script can't be queried".to_owned())
+}
+
+#[async_trait]
+impl DBConnection for ScriptConnection {
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error> {
+        self.statements.push(query.to_owned());
+        Ok(AnyQueryResult::default())
+    }
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error> {
+        /* every other piece between the NULs is a marker's index */
+        let mut statement = String::new();
+        for (i, piece) in query.split('\0').enumerate() {
+            if i % 2 == 0 {
+                statement.push_str(piece);
+                continue;
+            }
+            match piece
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.get(index.checked_sub(1)?))
+            {
+                Some(value) => statement.push_str(&value.to_literal(self.driver)),
+                None => return Err(sqlx::Error::Protocol(format!("no value #{}", piece))),
+            }
+        }
+        self.execute(&statement).await
+    }
+    async fn fetch_has_rows(
+        &mut self,
+        _query: &str,
+        _values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error> {
+        Err(not_queryable())
+    }
+    async fn fetch_rows_as_text(&mut self, _query: &str) -> Result<TextResultSet, sqlx::Error> {
+        Err(not_queryable())
+    }
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        Err(not_queryable())
+    }
+    async fn describe_table(
+        &mut self,
+        _table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error> {
+        Err(not_queryable())
+    }
+    async fn unique_key_sets(
+        &mut self,
+        _table_name: &str,
+    ) -> Result<Vec<Vec<String>>, sqlx::Error> {
+        Err(not_queryable())
+    }
+    /* nothing gets bound, so a batch is only limited by how many rows were asked for */
+    fn max_bind_values(&self) -> usize {
+        usize::MAX
+    }
+    /* inlined literals are untyped in postgres, so they need no cast */
+    fn placeholder(&self, index: usize, _description: &FieldDescription) -> String {
+        value_marker(index)
+    }
+    fn upsert_clause(&self, key_fields: &[String], update_fields: &[&str]) -> String {
+        match self.driver {
+            DBDriver::MySql => on_duplicate_key_clause(key_fields, update_fields),
+            DBDriver::Postgres | DBDriver::Sqlite => on_conflict_clause(key_fields, update_fields),
+        }
+    }
+    fn default_value(&self, description: &FieldDescription) -> String {
+        match self.driver {
+            DBDriver::Sqlite => default_expression(description),
+            DBDriver::MySql | DBDriver::Postgres => "DEFAULT".to_owned(),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use super::{attach_keys, rows_to_text, DBConnection, TextResultSet};
+use crate::backend::database_handler::FieldDescription;
+use crate::backend::parser::split_sql_type;
+use crate::backend::sql_value::SqlValue;
+use async_trait::async_trait;
+use sqlx::any::AnyQueryResult;
+use sqlx::query::Query;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Executor, Sqlite, SqliteConnection};
+
+#[async_trait]
+impl DBConnection for SqliteConnection {
+    async fn execute(&mut self, query: &str) -> Result<AnyQueryResult, sqlx::Error> {
+        Executor::execute(self, query)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn execute_with_values(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<AnyQueryResult, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .execute(self)
+            .await
+            .map(AnyQueryResult::from)
+    }
+    async fn fetch_has_rows(
+        &mut self,
+        query: &str,
+        values: Vec<SqlValue>,
+    ) -> Result<bool, sqlx::Error> {
+        bind_values(sqlx::query(query), values)
+            .fetch_optional(self)
+            .await
+            .map(|row| row.is_some())
+    }
+    async fn fetch_rows_as_text(&mut self, query: &str) -> Result<TextResultSet, sqlx::Error> {
+        rows_to_text(Executor::fetch_all(self, query).await?)
+    }
+    async fn query_for_tables(&mut self) -> Result<Vec<String>, sqlx::Error> {
+        let qr_tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(qr_tables.into_iter().map(|table| table.0).collect())
+    }
+    async fn unique_key_sets(&mut self, table_name: &str) -> Result<Vec<Vec<String>>, sqlx::Error> {
+        let quoted_table = quote_identifier(table_name);
+
+        /* seq, name, unique, origin, partial */
+        let qr_indexes: Vec<(i64, String, i64, String, i64)> =
+            sqlx::query_as(format!("PRAGMA index_list({})", quoted_table).as_str())
+                .fetch_all(&mut *self)
+                .await?;
+        let mut key_sets: Vec<Vec<String>> = Vec::new();
+        for index in qr_indexes.iter().filter(|index| index.2 == 1) {
+            /* seqno, cid, name */
+            let qr_index_columns: Vec<(i64, i64, Option<String>)> = sqlx::query_as(
+                format!("PRAGMA index_info({})", quote_identifier(&index.1)).as_str(),
+            )
+            .fetch_all(&mut *self)
+            .await?;
+            key_sets.push(
+                qr_index_columns
+                    .into_iter()
+                    .filter_map(|column| column.2)
+                    .collect(),
+            );
+        }
+
+        /* a primary key aliasing the rowid has no index of its own */
+        let qr_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+            sqlx::query_as(format!("PRAGMA table_info({})", quoted_table).as_str())
+                .fetch_all(&mut *self)
+                .await?;
+        let mut pk_columns: Vec<(i64, String)> = qr_columns
+            .into_iter()
+            .filter(|column| column.5 > 0)
+            .map(|column| (column.5, column.1))
+            .collect();
+        pk_columns.sort();
+        let pk_columns: Vec<String> = pk_columns.into_iter().map(|column| column.1).collect();
+        if !pk_columns.is_empty() && !key_sets.contains(&pk_columns) {
+            key_sets.push(pk_columns);
+        }
+        Ok(key_sets)
+    }
+    /* SQLITE_MAX_VARIABLE_NUMBER of the bundled sqlite */
+    fn max_bind_values(&self) -> usize {
+        32766
+    }
+    fn default_value(&self, description: &FieldDescription) -> String {
+        default_expression(description)
+    }
+    /* last_insert_rowid() is the rowid of the last row, a single statement's rows come right before it */
+    async fn inserted_ids(
+        &mut self,
+        result: &AnyQueryResult,
+        row_count: usize,
+    ) -> Result<Option<Vec<i64>>, sqlx::Error> {
+        Ok(match result.last_insert_id() {
+            Some(last_id) if last_id > 0 => {
+                Some((last_id + 1 - row_count as i64..=last_id).collect())
+            }
+            _ => None,
+        })
+    }
+    async fn describe_table(
+        &mut self,
+        table_name: &str,
+    ) -> Result<Vec<FieldDescription>, sqlx::Error> {
+        /* PRAGMAs can't take bound parameters, so quote the name by hand */
+        let quoted_table = quote_identifier(table_name);
+
+        /* cid, name, type, notnull, dflt_value, pk */
+        let qr_columns: Vec<(i64, String, String, i64, Option<String>, i64)> =
+            sqlx::query_as(format!("PRAGMA table_info({})", quoted_table).as_str())
+                .fetch_all(&mut *self)
+                .await?;
+
+        /* like mysql, only single column unique indexes mark the field as UNI */
+        let unique_columns: Vec<String> = self
+            .unique_key_sets(table_name)
+            .await?
+            .into_iter()
+            .filter(|key_set| key_set.len() == 1)
+            .map(|mut key_set| key_set.remove(0))
+            .collect();
+
+        /* id, seq, table, from, to, on_update, on_delete, match */
+        let qr_foreign_keys: Vec<(
+            i64,
+            i64,
+            String,
+            String,
+            Option<String>,
+            String,
+            String,
+            String,
+        )> = sqlx::query_as(format!("PRAGMA foreign_key_list({})", quoted_table).as_str())
+            .fetch_all(&mut *self)
+            .await?;
+        /* a missing "to" column means the referenced table's primary key */
+        let mut foreign_keys = Vec::new();
+        for (_, _, referenced_table, from, to, _, _, _) in qr_foreign_keys {
+            let to = match to {
+                Some(to) => to,
+                None => {
+                    let referenced_pk: Vec<(i64, String, String, i64, Option<String>, i64)> =
+                        sqlx::query_as(
+                            format!("PRAGMA table_info({})", quote_identifier(&referenced_table))
+                                .as_str(),
+                        )
+                        .fetch_all(&mut *self)
+                        .await?;
+                    match referenced_pk.into_iter().find(|column| column.5 > 0) {
+                        Some(column) => column.1,
+                        None => continue,
+                    }
+                }
+            };
+            foreign_keys.push((from, referenced_table, to));
+        }
+
+        /* seq, name, unique, origin, partial */
+        let qr_indexes: Vec<(i64, String, i64, String, i64)> =
+            sqlx::query_as(format!("PRAGMA index_list({})", quoted_table).as_str())
+                .fetch_all(&mut *self)
+                .await?;
+        let mut index_columns = Vec::new();
+        for index in qr_indexes {
+            /* seqno, cid, name */
+            let qr_index_columns: Vec<(i64, i64, Option<String>)> = sqlx::query_as(
+                format!("PRAGMA index_info({})", quote_identifier(&index.1)).as_str(),
+            )
+            .fetch_all(&mut *self)
+            .await?;
+            for column in qr_index_columns.into_iter().filter_map(|column| column.2) {
+                index_columns.push((index.1.clone(), column));
+            }
+        }
+
+        let pk_count = qr_columns.iter().filter(|column| column.5 > 0).count();
+        let mut fields: Vec<FieldDescription> = qr_columns
+            .into_iter()
+            .map(|(_, name, declared_type, notnull, default, pk)| {
+                /* an INTEGER PRIMARY KEY aliases the rowid, so it autoincrements */
+                let is_auto_increment =
+                    pk > 0 && pk_count == 1 && declared_type.eq_ignore_ascii_case("integer");
+                let field_type = normalize_type(&declared_type);
+                let (sql_type, type_args) = split_sql_type(&field_type);
+                let type_args: Vec<u64> = type_args
+                    .unwrap_or_default()
+                    .trim_matches(|c| c == '(' || c == ')')
+                    .split(',')
+                    .filter_map(|arg| arg.trim().parse().ok())
+                    .collect();
+                let key = if pk > 0 {
+                    "PRI"
+                } else if unique_columns.contains(&name) {
+                    "UNI"
+                } else {
+                    ""
+                };
+                /* sqlite doesn't enforce any of these, but the declared type still tells the intent */
+                let (max_length, numeric_precision, numeric_scale) = match sql_type.trim() {
+                    "char" | "varchar" => (type_args.first().copied(), None, None),
+                    "decimal" | "numeric" => {
+                        (None, type_args.first().copied(), type_args.get(1).copied())
+                    }
+                    _ => (None, None, None),
+                };
+                FieldDescription {
+                    field: name,
+                    is_unsigned: field_type.contains("unsigned"),
+                    field_type,
+                    null: if notnull == 0 && pk == 0 { "YES" } else { "NO" }.to_owned(),
+                    key: key.to_owned(),
+                    default,
+                    extra: if is_auto_increment {
+                        "auto_increment".to_owned()
+                    } else {
+                        String::new()
+                    },
+                    max_length,
+                    numeric_precision,
+                    numeric_scale,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        attach_keys(&mut fields, foreign_keys, index_columns);
+        Ok(fields)
+    }
+}
+
+/* sqlite has no DEFAULT keyword in VALUES, but dflt_value is already an SQL expression */
+pub(super) fn default_expression(description: &FieldDescription) -> String {
+    description
+        .default
+        .clone()
+        .unwrap_or_else(|| "NULL".to_owned())
+}
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/* sqlite keeps whatever type name the table was created with, so lowercase it for the parser */
+fn normalize_type(declared_type: &str) -> String {
+    let declared_type = declared_type.trim().to_lowercase();
+    match declared_type.as_str() {
+        "integer" => "int".to_owned(),
+        "" => "text".to_owned(),
+        _ => declared_type,
+    }
+}
+
+fn bind_values<'q>(
+    mut sql_query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    values: Vec<SqlValue>,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    for value in values {
+        sql_query = match value {
+            SqlValue::Null => sql_query.bind(None::<String>),
+            SqlValue::Int(val) => sql_query.bind(val),
+            SqlValue::Float(val) => sql_query.bind(val),
+            /* sqlx can't bind decimals to sqlite, its NUMERIC affinity converts the text back */
+            SqlValue::Decimal(val) => sql_query.bind(val.to_string()),
+            SqlValue::Bool(val) => sql_query.bind(val),
+            SqlValue::Text(val) => sql_query.bind(val),
+            SqlValue::Date(val) => sql_query.bind(val),
+            SqlValue::DateTime(val) => sql_query.bind(val),
+            SqlValue::DateTimeTz(val) => sql_query.bind(val),
+        };
+    }
+    sql_query
+}
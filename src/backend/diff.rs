@@ -0,0 +1,211 @@
+use super::csv_handler::{DataEntry, ImportedData};
+use super::database_handler::{FieldDescription, TableField};
+use super::db_connection::TextResultSet;
+use super::sql_value::SqlValue;
+use std::collections::HashMap;
+use std::error::Error;
+
+/* What an import would do to the table, row by row */
+#[derive(Debug)]
+pub struct TableDiff {
+    pub key_field: String,
+    pub rows: Vec<RowDiff>,
+}
+#[derive(Debug)]
+pub struct RowDiff {
+    /* index of the row in ImportedData::data */
+    pub row_index: usize,
+    pub key: String,
+    pub status: RowStatus,
+}
+#[derive(Debug)]
+pub enum RowStatus {
+    New,
+    Changed(Vec<FieldChange>),
+    Unchanged,
+}
+#[derive(Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: String,
+}
+
+impl TableDiff {
+    /* (new, changed, unchanged) */
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.rows
+            .iter()
+            .fold((0, 0, 0), |counts, row| match row.status {
+                RowStatus::New => (counts.0 + 1, counts.1, counts.2),
+                RowStatus::Changed(_) => (counts.0, counts.1 + 1, counts.2),
+                RowStatus::Unchanged => (counts.0, counts.1, counts.2 + 1),
+            })
+    }
+}
+
+/* Matches csv rows to `db_rows` on `key_field` and compares every mapped field */
+pub fn diff_against_table(
+    csv_data: &ImportedData,
+    fields: &[TableField],
+    key_field: &str,
+    db_rows: &TextResultSet,
+) -> Result<TableDiff, Box<dyn Error + Send>> {
+    let key = match fields
+        .iter()
+        .find(|field| field.description.field == key_field && field.mapped_to_col.is_some())
+    {
+        Some(key) => key,
+        None => {
+            return Err(to_error(format!(
+                "\"{}\" isn't mapped to any column",
+                key_field
+            )))
+        }
+    };
+    let key_col = key.mapped_to_col.unwrap();
+    let db_key_col = match db_rows.column_index(key_field) {
+        Some(db_key_col) => db_key_col,
+        None => return Err(to_error(format!("Table has no field \"{}\"", key_field))),
+    };
+
+    /* first row wins if the table has duplicate keys */
+    let mut db_rows_by_key: HashMap<String, &Vec<Option<String>>> = HashMap::new();
+    for db_row in db_rows.rows.iter() {
+        let db_key = normalized_key(&key.description, db_row[db_key_col].as_deref());
+        db_rows_by_key.entry(db_key).or_insert(db_row);
+    }
+
+    /* (field, col in the grid, col in db_rows) */
+    let mapped_fields: Vec<(&FieldDescription, usize, Option<usize>)> = fields
+        .iter()
+        .filter_map(|field| {
+            field.mapped_to_col.map(|col| {
+                (
+                    &field.description,
+                    col,
+                    db_rows.column_index(&field.description.field),
+                )
+            })
+        })
+        .collect();
+
+    let mut diff = TableDiff {
+        key_field: key_field.to_owned(),
+        rows: Vec::new(),
+    };
+    let start_i: usize = csv_data.are_headers.into();
+    for row_index in start_i..csv_data.data.rows() {
+        let csv_key = &csv_data.data[row_index][key_col].data;
+        let normalized_csv_key = normalized_key(&key.description, Some(csv_key));
+        let status = match db_rows_by_key.get(&normalized_csv_key) {
+            None => RowStatus::New,
+            Some(db_row) => {
+                let mut changes = Vec::new();
+                for (description, col, db_col) in mapped_fields.iter() {
+                    let after = &csv_data.data[row_index][*col].data;
+                    let before = db_col.and_then(|db_col| db_row[db_col].clone());
+                    if !values_equal(description, before.as_deref(), after) {
+                        changes.push(FieldChange {
+                            field: description.field.clone(),
+                            before,
+                            after: after.clone(),
+                        });
+                    }
+                }
+                if changes.is_empty() {
+                    RowStatus::Unchanged
+                } else {
+                    RowStatus::Changed(changes)
+                }
+            }
+        };
+        diff.rows.push(RowDiff {
+            row_index,
+            key: csv_key.clone(),
+            status,
+        });
+    }
+    Ok(diff)
+}
+
+/* Converts like an insert would, so "12.5" and "12.5000" in a decimal field are the same */
+fn typed_value(description: &FieldDescription, value: Option<&str>) -> Option<SqlValue> {
+    match value {
+        None => Some(SqlValue::Null),
+        Some(value) => SqlValue::from_cell(&DataEntry {
+            data: value.to_owned(),
+            curr_field_description: Some(description.clone()),
+            is_parsed: None,
+        })
+        .ok(),
+    }
+}
+fn values_equal(description: &FieldDescription, before: Option<&str>, after: &str) -> bool {
+    match (
+        typed_value(description, before),
+        typed_value(description, Some(after)),
+    ) {
+        (Some(before), Some(after)) => before == after,
+        _ => before == Some(after),
+    }
+}
+pub fn normalized_key(description: &FieldDescription, value: Option<&str>) -> String {
+    match typed_value(description, value) {
+        Some(SqlValue::Decimal(val)) => val.normalize().to_string(),
+        Some(val) => val.to_string(),
+        None => value.unwrap_or_default().to_owned(),
+    }
+}
+
+fn to_error(message: String) -> Box<dyn Error + Send> {
+    <String as Into<Box<dyn Error + Send + Sync>>>::into(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalized_key;
+    use crate::backend::database_handler::FieldDescription;
+
+    fn field(field_type: &str) -> FieldDescription {
+        FieldDescription {
+            field: "sku".to_owned(),
+            field_type: field_type.to_owned(),
+            null: "YES".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keys_compare_as_their_fields_type() {
+        let decimal = field("decimal(15,4)");
+        assert_eq!(
+            normalized_key(&decimal, Some("12.5000")),
+            normalized_key(&decimal, Some("12.5"))
+        );
+        let int = field("int(11)");
+        assert_eq!(
+            normalized_key(&int, Some(" 42")),
+            normalized_key(&int, Some("42"))
+        );
+        let text = field("varchar(20)");
+        assert_ne!(
+            normalized_key(&text, Some("A1")),
+            normalized_key(&text, Some("a1"))
+        );
+    }
+
+    #[test]
+    fn invalid_keys_are_kept_as_they_are() {
+        assert_eq!(normalized_key(&field("int(11)"), Some("abc")), "abc");
+    }
+
+    #[test]
+    fn null_key_differs_from_the_text_null() {
+        let text = field("varchar(20)");
+        assert_ne!(
+            normalized_key(&text, None),
+            normalized_key(&text, Some("NULL"))
+        );
+    }
+}
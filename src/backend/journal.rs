@@ -0,0 +1,117 @@
+use super::db_connection::TextResultSet;
+use super::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/* An append that got committed, with enough to delete exactly its rows later */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportRecord {
+    pub id: u64,
+    /* "host/database", so imports from other databases don't show up */
+    pub database: String,
+    pub table: String,
+    pub time: String,
+    pub key_field: String,
+    /* the rows as they were right after inserting, keyed by `key_field` */
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+    pub is_undone: bool,
+}
+impl ImportRecord {
+    pub fn keys(&self) -> Vec<String> {
+        let key_col = match self.columns.iter().position(|col| col == &self.key_field) {
+            Some(key_col) => key_col,
+            None => return Vec::new(),
+        };
+        self.rows
+            .iter()
+            .filter_map(|row| row[key_col].clone())
+            .collect()
+    }
+    /* (rows changed since the import, rows already gone) */
+    pub fn compare_with(&self, current_rows: &TextResultSet) -> (usize, usize) {
+        let key_col = self.columns.iter().position(|col| col == &self.key_field);
+        let current_key_col = current_rows.column_index(&self.key_field);
+        let (key_col, current_key_col) = match (key_col, current_key_col) {
+            (Some(key_col), Some(current_key_col)) => (key_col, current_key_col),
+            _ => return (0, self.rows.len()),
+        };
+        let current_rows_by_key: HashMap<&Option<String>, &Vec<Option<String>>> = current_rows
+            .rows
+            .iter()
+            .map(|current_row| (&current_row[current_key_col], current_row))
+            .collect();
+        let mut modified = 0;
+        let mut missing = 0;
+        for row in self.rows.iter() {
+            match current_rows_by_key.get(&row[key_col]) {
+                None => missing += 1,
+                Some(current_row) => {
+                    let is_modified = self.columns.iter().zip(row).any(|(col, value)| {
+                        current_rows
+                            .column_index(col)
+                            .is_none_or(|current_col| &current_row[current_col] != value)
+                    });
+                    if is_modified {
+                        modified += 1;
+                    }
+                }
+            }
+        }
+        (modified, missing)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ImportJournal {
+    pub records: Vec<ImportRecord>,
+}
+/* Appends finishing together each load, change and save the journal, one at a time
+ * so neither drops the other's record */
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+impl ImportJournal {
+    fn path() -> Option<PathBuf> {
+        storage::data_file("import_journal.json")
+    }
+    /* A missing journal just means nothing was imported yet */
+    pub fn load() -> ImportJournal {
+        storage::load(ImportJournal::path().as_deref(), "import journal")
+    }
+    fn save(&self) -> Result<(), Box<dyn Error + Send>> {
+        storage::save(ImportJournal::path().as_deref(), "import journal", self)
+    }
+    /* Changes the journal as saved right now, not a copy loaded before */
+    fn update(change: impl FnOnce(&mut ImportJournal)) -> Result<(), Box<dyn Error + Send>> {
+        let _guard = JOURNAL_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut journal = ImportJournal::load();
+        change(&mut journal);
+        journal.save()
+    }
+    pub fn add(mut record: ImportRecord) -> Result<(), Box<dyn Error + Send>> {
+        ImportJournal::update(|journal| {
+            record.id = journal
+                .records
+                .iter()
+                .map(|record| record.id + 1)
+                .max()
+                .unwrap_or(0);
+            journal.records.push(record);
+        })
+    }
+    pub fn get(&self, id: u64) -> Option<&ImportRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+    pub fn mark_undone(id: u64) -> Result<(), Box<dyn Error + Send>> {
+        ImportJournal::update(|journal| {
+            if let Some(record) = journal.records.iter_mut().find(|record| record.id == id) {
+                record.is_undone = true;
+            }
+        })
+    }
+}
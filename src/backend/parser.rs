@@ -1,76 +1,87 @@
 use super::csv_handler::DataEntry;
-use chrono::prelude::{NaiveDate, NaiveDateTime};
+use super::database_handler::ForeignKey;
+use super::sql_value::{parse_bool, parse_timestamp};
+use chrono::prelude::NaiveDate;
+use std::collections::HashSet;
 use std::{error::Error, num::ParseFloatError, num::ParseIntError, sync::Arc};
 
 pub fn parse(cell: &mut DataEntry) {
-    let sql_type: String;
-    let type_args: Option<String>;
-    /* extract sql type and args */
-    if let Some(arg_index) = cell
-        .curr_field_description
-        .as_ref()
-        .unwrap()
-        .field_type
-        .find("(")
-    {
-        sql_type = cell
-            .curr_field_description
-            .as_ref()
-            .unwrap()
-            .field_type
-            .get(0..arg_index)
-            .unwrap()
-            .to_owned();
-
-        if let Some(args_res) = cell
-            .curr_field_description
-            .as_ref()
-            .unwrap()
-            .field_type
-            .get(
-                arg_index
-                    ..cell
-                        .curr_field_description
-                        .as_ref()
-                        .unwrap()
-                        .field_type
-                        .len(),
-            )
-        {
-            type_args = Some(args_res.to_owned());
-        } else {
-            type_args = None;
-        }
-    } else {
-        sql_type = cell
-            .curr_field_description
-            .as_ref()
-            .unwrap()
-            .field_type
-            .to_owned();
-        type_args = None;
+    let description = cell.curr_field_description.as_ref().unwrap();
+    /* goes in as NULL, see SqlValue::from_cell */
+    if cell.data.is_empty() && description.null == "YES" {
+        cell.is_parsed = Some(Ok(()));
+        return;
     }
+    let (sql_type, type_args) = split_sql_type(&description.field_type);
+    /* information_schema has these for every driver, the type's args are only a fallback */
+    let num_args = process_args(type_args.clone()).unwrap_or_default();
+    let arg = |i: usize| num_args.get(i).map(|arg| *arg as u64);
+    let max_length = description.max_length.or(arg(0));
+    let precision = description.numeric_precision.or(arg(0));
+    let scale = description.numeric_scale.or(arg(1));
 
     match sql_type.as_str() {
-        "char" => parse_char(cell, type_args),
-        "varchar" => parse_varchar(cell, type_args),
+        "char" => parse_char(cell, max_length),
+        "varchar" => parse_varchar(cell, max_length),
         "text" => parse_text(cell, type_args),
-        "double" => parse_double(cell, type_args),
-        "decimal" => parse_decimal(cell, type_args),
-        "int" => parse_int(cell, type_args),
+        "double" | "float" | "real" => parse_double(cell, type_args),
+        "decimal" | "numeric" => parse_decimal(cell, precision, scale),
+        "int" | "integer" => parse_int(cell, type_args),
+        "smallint" => parse_smallint(cell),
+        "bigint" => parse_bigint(cell),
         "tinyint" => parse_tinyint(cell, type_args),
         "date" => parse_date(cell),
-        "datetime" => parse_datetime(cell),
+        "datetime" | "timestamp" | "timestamptz" => parse_datetime(cell),
+        "boolean" | "bool" => parse_boolean(cell),
+        "uuid" => parse_uuid(cell),
+        "json" | "jsonb" => parse_json(cell),
         "enum" => parse_enum(cell, type_args),
-        _ => {
-            let arc: Arc<dyn Error + Send + Sync> = Arc::from(<String as Into<
-                Box<dyn Error + Send + Sync>,
-            >>::into(sql_type));
-            cell.is_parsed = Some(Err(arc))
-        }
+        /* eg. time, bytea or arrays, the database is left to reject what doesn't fit */
+        _ => cell.is_parsed = Some(Ok(())),
     }
 }
 
+/* Runs after parse(), cells that already failed keep their own error */
+pub fn check_foreign_key(
+    cell: &mut DataEntry,
+    foreign_key: &ForeignKey,
+    referenced_values: &HashSet<String>,
+) {
+    if !matches!(cell.is_parsed, Some(Ok(()))) {
+        return;
+    }
+    let is_nullable = cell
+        .curr_field_description
+        .as_ref()
+        .is_some_and(|description| description.null == "YES");
+    if cell.data.is_empty() && is_nullable {
+        return;
+    }
+    if !referenced_values.contains(&cell.data) {
+        cell.is_parsed = Some(Err(Arc::from(<String as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into(format!(
+            "No {} \"{}\" in table \"{}\"",
+            foreign_key.field, cell.data, foreign_key.table
+        )))))
+    }
+}
+
+/* "decimal(15,4) unsigned" -> ("decimal", Some("(15,4)")), "int unsigned" -> ("int", None) */
+pub fn split_sql_type(field_type: &str) -> (String, Option<String>) {
+    let field_type = field_type.trim();
+    let type_end = field_type
+        .find(|c: char| c == '(' || c.is_whitespace())
+        .unwrap_or(field_type.len());
+    let type_args = field_type.find('(').map(|args_start| {
+        let args_end = field_type[args_start..]
+            .find(')')
+            .map_or(field_type.len(), |args_len| args_start + args_len + 1);
+        field_type[args_start..args_end].to_owned()
+    });
+    (field_type[..type_end].to_lowercase(), type_args)
+}
+
 fn process_args(args: Option<String>) -> Option<Vec<u32>> {
     if let Some(args) = args {
         //remove the "()" at start and end
@@ -99,31 +110,23 @@ fn process_args(args: Option<String>) -> Option<Vec<u32>> {
         return None;
     }
 }
-fn parse_char(cell: &mut DataEntry, args: Option<String>) {
+fn parse_char(cell: &mut DataEntry, max_length: Option<u64>) {
     //CHAR(5) = 'chart ' - CHAR adds spaces to values on the right to the specified length
-    if let Some(num_args) = process_args(args) {
-        let count = cell.data.chars().count() as u32;
-        if count > num_args[0] {
-            cell.is_parsed = Some(Ok(()));
-        } else {
-            cell.is_parsed = Some(Err(Arc::from(<&str as Into<
-                Box<dyn Error + Send + Sync>,
-            >>::into("Too long"))))
-        }
-    }
+    parse_length(cell, max_length)
 }
 
-fn parse_varchar(cell: &mut DataEntry, args: Option<String>) {
+fn parse_varchar(cell: &mut DataEntry, max_length: Option<u64>) {
     //VARCHAR(5) = 'chart'
-    if let Some(num_args) = process_args(args) {
-        let count = cell.data.chars().count() as u32;
-        if count <= num_args[0] {
-            cell.is_parsed = Some(Ok(()))
-        } else {
-            cell.is_parsed = Some(Err(Arc::from(<&str as Into<
-                Box<dyn Error + Send + Sync>,
-            >>::into("Too long"))))
-        }
+    parse_length(cell, max_length)
+}
+fn parse_length(cell: &mut DataEntry, max_length: Option<u64>) {
+    let count = cell.data.chars().count() as u64;
+    if matches!(max_length, Some(max_length) if count > max_length) {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into("Too long"))))
+    } else {
+        cell.is_parsed = Some(Ok(()))
     }
 }
 fn parse_text(cell: &mut DataEntry, args: Option<String>) {
@@ -132,55 +135,64 @@ fn parse_text(cell: &mut DataEntry, args: Option<String>) {
 }
 fn parse_double(cell: &mut DataEntry, args: Option<String>) {
     //Double(6,2) = 9999.99
-    if let Some(num_args) = process_args(args) {
-        let num_parse_res: Result<f32, ParseFloatError> = cell.data.parse();
-        if let Ok(_) = num_parse_res {
-            let dot_i = cell.data.find(".").unwrap();
-            // -1 cuz the dot is there
-            let count_to_dot = cell.data.chars().count() as u32;
-            let count_from_dot = cell.data.chars().skip(dot_i + 1).count() as u32;
-            if count_to_dot - 1 <= num_args[0] && count_from_dot > num_args[1] {
+    match process_args(args).as_deref() {
+        Some([precision, scale]) => {
+            parse_decimal(cell, Some(*precision as u64), Some(*scale as u64))
+        }
+        /* a plain double, or postgres' double precision, takes any number */
+        _ => {
+            if cell.data.parse::<f64>().is_ok() {
                 cell.is_parsed = Some(Ok(()));
             } else {
                 cell.is_parsed = Some(Err(Arc::from(<&str as Into<
                     Box<dyn Error + Send + Sync>,
-                >>::into("too many numbers"))))
+                >>::into("Not a number"))))
             }
         }
-    } else {
-        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
-            Box<dyn Error + Send + Sync>,
-        >>::into("Not a number"))))
     }
 }
-fn parse_decimal(cell: &mut DataEntry, args: Option<String>) {
+fn parse_decimal(cell: &mut DataEntry, precision: Option<u64>, scale: Option<u64>) {
     //DECIMAL(6,2) = 9999.99
-    if let Some(num_args) = process_args(args) {
-        let num_parse_res: Result<f32, ParseFloatError> = cell.data.parse();
-        if let Ok(_) = num_parse_res {
-            if let Some(dot_i) = cell.data.find(".") {
-                // -1 cuz the dot is there
-                let count_to_dot = cell.data.chars().count() as u32;
-                let count_from_dot = cell.data.chars().skip(dot_i + 1).count() as u32;
-                if count_to_dot - 1 <= num_args[0] && count_from_dot >= num_args[1] {
-                    cell.is_parsed = Some(Ok(()));
-                } else {
-                    cell.is_parsed = Some(Err(Arc::from(<&str as Into<
-                        Box<dyn Error + Send + Sync>,
-                    >>::into(
-                        "too many numbers"
-                    ))))
-                }
-            }
-        } else {
-            cell.is_parsed = Some(Err(Arc::from(<&str as Into<
-                Box<dyn Error + Send + Sync>,
-            >>::into("Not a number"))))
-        }
+    let num_parse_res: Result<f64, ParseFloatError> = cell.data.parse();
+    if num_parse_res.is_err() {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into("Not a number"))));
+        return;
+    }
+    if is_unsigned(cell) && cell.data.trim_start().starts_with('-') {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into(
+            "Negative number in an unsigned field"
+        ))));
+        return;
+    }
+    /* postgres' plain numeric has neither, and takes any number */
+    if precision.is_none() && scale.is_none() {
+        cell.is_parsed = Some(Ok(()));
+        return;
+    }
+    let precision = precision.unwrap_or(10);
+    let scale = scale.unwrap_or(0);
+    let digits = cell.data.trim_start_matches(['-', '+']);
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let whole_count = whole.trim_start_matches('0').chars().count() as u64;
+    let fraction_count = fraction.chars().count() as u64;
+    if whole_count + scale <= precision && fraction_count <= scale {
+        cell.is_parsed = Some(Ok(()));
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into("too many numbers"))))
     }
 }
 fn parse_int(cell: &mut DataEntry, args: Option<String>) {
-    let num_parse_res: Result<i32, ParseIntError> = cell.data.parse();
+    let num_parse_res: Result<(), ParseIntError> = if is_unsigned(cell) {
+        cell.data.parse::<u32>().map(|_| ())
+    } else {
+        cell.data.parse::<i32>().map(|_| ())
+    };
     if let Ok(_) = num_parse_res {
         cell.is_parsed = Some(Ok(()));
     } else {
@@ -191,9 +203,46 @@ fn parse_int(cell: &mut DataEntry, args: Option<String>) {
         ))))
     }
 }
+fn parse_smallint(cell: &mut DataEntry) {
+    //-32768 to 32767 signed
+    let num_parse_res: Result<(), ParseIntError> = if is_unsigned(cell) {
+        cell.data.parse::<u16>().map(|_| ())
+    } else {
+        cell.data.parse::<i16>().map(|_| ())
+    };
+    if num_parse_res.is_ok() {
+        cell.is_parsed = Some(Ok(()));
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into(
+            "Number too big or not a number."
+        ))))
+    }
+}
+fn parse_bigint(cell: &mut DataEntry) {
+    let num_parse_res: Result<(), ParseIntError> = if is_unsigned(cell) {
+        cell.data.parse::<u64>().map(|_| ())
+    } else {
+        cell.data.parse::<i64>().map(|_| ())
+    };
+    if num_parse_res.is_ok() {
+        cell.is_parsed = Some(Ok(()));
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into(
+            "Number too big or not a number."
+        ))))
+    }
+}
 fn parse_tinyint(cell: &mut DataEntry, args: Option<String>) {
     //max val 255 or -128 to 127 signed
-    let num_parse_res: Result<i8, ParseIntError> = cell.data.parse();
+    let num_parse_res: Result<(), ParseIntError> = if is_unsigned(cell) {
+        cell.data.parse::<u8>().map(|_| ())
+    } else {
+        cell.data.parse::<i8>().map(|_| ())
+    };
     if let Ok(_) = num_parse_res {
         cell.is_parsed = Some(Ok(()))
     } else {
@@ -223,11 +272,11 @@ fn parse_date(cell: &mut DataEntry) {
     }
 }
 fn parse_datetime(cell: &mut DataEntry) {
-    //YYYY-MM-DD HH:MM:SS, also ’0000-00-00’
+    //YYYY-MM-DD HH:MM:SS, with an optional offset like postgres' +00, also ’0000-00-00’
     if cell.data == "0000-00-00 00:00:00" {
         cell.is_parsed = Some(Ok(()))
     } else {
-        match NaiveDateTime::parse_from_str(&cell.data, "%Y-%m-%d %H:%M:%S") {
+        match parse_timestamp(&cell.data) {
             Ok(_) => cell.is_parsed = Some(Ok(())),
             Err(e) => {
                 cell.is_parsed = Some(Err(Arc::from(<String as Into<
@@ -240,6 +289,59 @@ fn parse_datetime(cell: &mut DataEntry) {
         }
     }
 }
-fn parse_enum(cell: &mut DataEntry, args: Option<String>) {
-    todo!()
+fn parse_boolean(cell: &mut DataEntry) {
+    if parse_bool(&cell.data).is_some() {
+        cell.is_parsed = Some(Ok(()))
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into("Not true or false"))))
+    }
+}
+fn parse_uuid(cell: &mut DataEntry) {
+    //a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11, postgres also takes it without dashes or in braces
+    let data = cell.data.trim();
+    let data = data
+        .strip_prefix('{')
+        .and_then(|data| data.strip_suffix('}'))
+        .unwrap_or(data);
+    let digits: Vec<char> = data.chars().filter(|c| *c != '-').collect();
+    if digits.len() == 32 && digits.iter().all(|c| c.is_ascii_hexdigit()) {
+        cell.is_parsed = Some(Ok(()))
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<&str as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into("Not a UUID"))))
+    }
+}
+fn parse_json(cell: &mut DataEntry) {
+    match serde_json::from_str::<serde_json::Value>(&cell.data) {
+        Ok(_) => cell.is_parsed = Some(Ok(())),
+        Err(e) => {
+            cell.is_parsed = Some(Err(Arc::from(<String as Into<
+                Box<dyn Error + Send + Sync>,
+            >>::into(format!(
+                "Invalid JSON: {}",
+                e
+            )))))
+        }
+    }
+}
+fn parse_enum(cell: &mut DataEntry, _args: Option<String>) {
+    let enum_values = &cell.curr_field_description.as_ref().unwrap().enum_values;
+    if enum_values.contains(&cell.data) {
+        cell.is_parsed = Some(Ok(()))
+    } else {
+        cell.is_parsed = Some(Err(Arc::from(<String as Into<
+            Box<dyn Error + Send + Sync>,
+        >>::into(format!(
+            "Not one of: {}",
+            enum_values.join(", ")
+        )))))
+    }
+}
+fn is_unsigned(cell: &DataEntry) -> bool {
+    cell.curr_field_description
+        .as_ref()
+        .is_some_and(|description| description.is_unsigned)
 }
@@ -0,0 +1,309 @@
+use super::database_handler::{DBLoginData, SslMode};
+use super::db_connection::DBDriver;
+use super::storage;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::error::Error;
+use std::path::PathBuf;
+
+/* OWASP's recommendation for PBKDF2-HMAC-SHA256 */
+const KDF_ROUNDS: u32 = 600_000;
+/* XChaCha20's nonce is long enough to be picked at random for every secret */
+const NONCE_LENGTH: usize = 24;
+
+/* A saved connection, everything but the password is kept as is */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub driver: DBDriver,
+    pub host: String,
+    pub port: String,
+    #[serde(default)]
+    pub socket: String,
+    pub database: String,
+    pub user_name: String,
+    pub ssl_mode: SslMode,
+    pub ssl_ca: String,
+    /* hex of nonce and ciphertext with its tag, see MasterKey::encrypt */
+    password: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct ProfileStore {
+    /* hex, mixed into the master passphrase */
+    salt: String,
+    /* an encrypted empty string, decrypting it tells whether the passphrase is right */
+    verifier: Option<String>,
+    pub profiles: Vec<Profile>,
+    /* where it was loaded from and gets saved to */
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+impl ProfileStore {
+    pub fn load() -> ProfileStore {
+        ProfileStore::load_from(storage::config_file("profiles.json"))
+    }
+    fn load_from(path: Option<PathBuf>) -> ProfileStore {
+        let mut store: ProfileStore = storage::load(path.as_deref(), "connection profiles");
+        store.path = path;
+        store
+    }
+    fn save(&self) -> Result<(), Box<dyn Error + Send>> {
+        storage::save(self.path.as_deref(), "connection profiles", self)
+    }
+    /* No passphrase has been picked yet, the first one unlocking the store becomes it */
+    pub fn is_new(&self) -> bool {
+        self.verifier.is_none()
+    }
+    /* What the master passphrase is mixed with, a new store gets a random one */
+    pub fn salt(&mut self) -> Result<Vec<u8>, Box<dyn Error + Send>> {
+        if self.is_new() {
+            self.salt = hex::encode(rand::random::<[u8; 16]>());
+        }
+        match hex::decode(&self.salt) {
+            Ok(salt) => Ok(salt),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+    /* Checks `key`, derived from the passphrase and salt(), against the store.
+     * A new store takes it as its master key from now on */
+    pub fn unlock(&mut self, key: MasterKey) -> Result<MasterKey, Box<dyn Error + Send>> {
+        match &self.verifier {
+            Some(verifier) => {
+                if key.decrypt(verifier).is_err() {
+                    return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                        "Wrong master passphrase",
+                    ));
+                }
+            }
+            None => {
+                self.verifier = Some(key.encrypt(""));
+                self.save()?;
+            }
+        }
+        Ok(key)
+    }
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+    /* The password only comes along when the store is unlocked */
+    pub fn login_data(
+        &self,
+        name: &str,
+        key: Option<&MasterKey>,
+    ) -> Result<DBLoginData, Box<dyn Error + Send>> {
+        let profile = match self.get(name) {
+            Some(profile) => profile,
+            None => {
+                return Err(<String as Into<Box<dyn Error + Send + Sync>>>::into(
+                    format!("No profile named \"{}\"", name),
+                ))
+            }
+        };
+        let password = match key {
+            Some(key) => key.decrypt(&profile.password)?,
+            None => String::new(),
+        };
+        Ok(DBLoginData {
+            driver: profile.driver,
+            user_name: profile.user_name.clone(),
+            database: profile.database.clone(),
+            host: profile.host.clone(),
+            port: profile.port.clone(),
+            socket: profile.socket.clone(),
+            password,
+            ssl_mode: profile.ssl_mode,
+            ssl_ca: profile.ssl_ca.clone(),
+            should_remember: true,
+            is_verified: false,
+        })
+    }
+    /* Saves `login_data` under `name`, replacing the profile already called that */
+    pub fn remember(
+        &mut self,
+        name: &str,
+        login_data: &DBLoginData,
+        key: &MasterKey,
+    ) -> Result<(), Box<dyn Error + Send>> {
+        let profile = Profile {
+            name: name.to_owned(),
+            driver: login_data.driver,
+            host: login_data.host.clone(),
+            port: login_data.port.clone(),
+            socket: login_data.socket.clone(),
+            database: login_data.database.clone(),
+            user_name: login_data.user_name.clone(),
+            ssl_mode: login_data.ssl_mode,
+            ssl_ca: login_data.ssl_ca.clone(),
+            password: key.encrypt(&login_data.password),
+        };
+        match self
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name == name)
+        {
+            Some(old_profile) => *old_profile = profile,
+            None => self.profiles.push(profile),
+        }
+        self.save()
+    }
+    pub fn forget(&mut self, name: &str) -> Result<(), Box<dyn Error + Send>> {
+        self.profiles.retain(|profile| profile.name != name);
+        self.save()
+    }
+}
+
+/* The key derived from the master passphrase, secrets are sealed with XChaCha20-Poly1305 */
+pub struct MasterKey {
+    cipher: XChaCha20Poly1305,
+}
+/* never prints the key, Communication derives Debug */
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterKey(..)")
+    }
+}
+impl MasterKey {
+    /* Takes a good second on purpose, run it off the ui thread */
+    pub fn derive(passphrase: &str, salt: &[u8]) -> MasterKey {
+        MasterKey::derive_with_rounds(passphrase, salt, KDF_ROUNDS)
+    }
+    fn derive_with_rounds(passphrase: &str, salt: &[u8], rounds: u32) -> MasterKey {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+        MasterKey {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+    fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+        let ciphertext = self
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
+            .expect("XChaCha20-Poly1305 encrypts any password length");
+        hex::encode([&nonce[..], &ciphertext].concat())
+    }
+    fn decrypt(&self, secret: &str) -> Result<String, Box<dyn Error + Send>> {
+        let bytes = match hex::decode(secret) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(Box::new(e)),
+        };
+        if bytes.len() < NONCE_LENGTH {
+            return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                "Encrypted password is too short",
+            ));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+        let plaintext = match self.cipher.decrypt(XNonce::from_slice(nonce), ciphertext) {
+            Ok(plaintext) => plaintext,
+            Err(_) => {
+                return Err(<&str as Into<Box<dyn Error + Send + Sync>>>::into(
+                    "Encrypted password doesn't match the master passphrase",
+                ))
+            }
+        };
+        match String::from_utf8(plaintext) {
+            Ok(plaintext) => Ok(plaintext),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MasterKey, ProfileStore};
+    use crate::backend::database_handler::{DBLoginData, SslMode};
+    use crate::backend::db_connection::DBDriver;
+
+    /* the real round count makes debug test runs crawl, the cipher doesn't care */
+    fn key_with_salt(passphrase: &str, salt: &[u8]) -> MasterKey {
+        MasterKey::derive_with_rounds(passphrase, salt, 10)
+    }
+    fn key(passphrase: &str) -> MasterKey {
+        key_with_salt(passphrase, b"0123456789abcdef")
+    }
+
+    #[test]
+    fn encrypted_password_round_trips() {
+        let key = key("correct horse");
+        for password in ["", "hunter2", "pässwörd with ünicode", &"x".repeat(1000)] {
+            let secret = key.encrypt(password);
+            assert_eq!(key.decrypt(&secret).unwrap(), password);
+        }
+    }
+
+    #[test]
+    fn same_password_encrypts_differently() {
+        let key = key("correct horse");
+        assert_ne!(key.encrypt("hunter2"), key.encrypt("hunter2"));
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let secret = key("correct horse").encrypt("hunter2");
+        assert!(key("battery staple").decrypt(&secret).is_err());
+    }
+
+    #[test]
+    fn tampered_secret_is_rejected() {
+        let key = key("correct horse");
+        let mut bytes = hex::decode(key.encrypt("hunter2")).unwrap();
+        for i in 0..bytes.len() {
+            bytes[i] ^= 1;
+            assert!(key.decrypt(&hex::encode(&bytes)).is_err(), "byte {}", i);
+            bytes[i] ^= 1;
+        }
+        assert!(key
+            .decrypt(&hex::encode(&bytes[..bytes.len() - 1]))
+            .is_err());
+        assert!(key.decrypt("00").is_err());
+        assert!(key.decrypt("not hex").is_err());
+    }
+
+    #[test]
+    fn remembered_profile_round_trips() {
+        let dir = std::env::temp_dir().join(format!("csql-test-{:016x}", rand::random::<u64>()));
+        let path = dir.join("profiles.json");
+        let login_data = DBLoginData {
+            driver: DBDriver::Postgres,
+            user_name: "saleor".to_owned(),
+            database: "shop".to_owned(),
+            host: "db.example.com".to_owned(),
+            port: "5432".to_owned(),
+            password: "hunter2".to_owned(),
+            ssl_mode: SslMode::VerifyCa,
+            ssl_ca: "/etc/ssl/ca.pem".to_owned(),
+            ..Default::default()
+        };
+
+        let mut store = ProfileStore::load_from(Some(path.clone()));
+        assert!(store.is_new());
+        let salt = store.salt().unwrap();
+        let key = store.unlock(key_with_salt("correct horse", &salt)).unwrap();
+        store.remember("shop", &login_data, &key).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+
+        let mut store = ProfileStore::load_from(Some(path.clone()));
+        assert!(!store.is_new());
+        let salt = store.salt().unwrap();
+        assert!(store
+            .unlock(key_with_salt("battery staple", &salt))
+            .is_err());
+        let key = store.unlock(key_with_salt("correct horse", &salt)).unwrap();
+        let loaded = store.login_data("shop", Some(&key)).unwrap();
+        assert_eq!(loaded.driver, DBDriver::Postgres);
+        assert_eq!(loaded.user_name, "saleor");
+        assert_eq!(loaded.host, "db.example.com");
+        assert_eq!(loaded.ssl_mode, SslMode::VerifyCa);
+        assert_eq!(loaded.ssl_ca, "/etc/ssl/ca.pem");
+        assert_eq!(loaded.password, "hunter2");
+        /* still locked, everything but the password */
+        assert_eq!(store.login_data("shop", None).unwrap().password, "");
+
+        store.forget("shop").unwrap();
+        assert!(ProfileStore::load_from(Some(path)).get("shop").is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,34 @@
+use super::storage;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+
+/* Oldest queries get dropped past this */
+const MAX_QUERIES: usize = 200;
+
+/* What was run in the SQL console, oldest first */
+#[derive(Serialize, Deserialize, Default)]
+pub struct QueryHistory {
+    pub queries: Vec<String>,
+}
+impl QueryHistory {
+    fn path() -> Option<PathBuf> {
+        storage::data_file("query_history.json")
+    }
+    pub fn load() -> QueryHistory {
+        storage::load(QueryHistory::path().as_deref(), "query history")
+    }
+    pub fn save(&self) -> Result<(), Box<dyn Error + Send>> {
+        storage::save(QueryHistory::path().as_deref(), "query history", self)
+    }
+    /* A query run again moves to the end instead of showing up twice */
+    pub fn add(&mut self, query: &str) -> Result<(), Box<dyn Error + Send>> {
+        let query = query.trim();
+        self.queries.retain(|old_query| old_query != query);
+        self.queries.push(query.to_owned());
+        if self.queries.len() > MAX_QUERIES {
+            self.queries.remove(0);
+        }
+        self.save()
+    }
+}
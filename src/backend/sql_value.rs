@@ -0,0 +1,237 @@
+use super::csv_handler::DataEntry;
+use super::db_connection::DBDriver;
+use super::parser::split_sql_type;
+use chrono::prelude::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/* A cell converted to the type its field expects, ready to be bound to a query */
+#[derive(Clone, Debug, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Decimal(Decimal),
+    Bool(bool),
+    Text(String),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    /* a timestamp written with its offset, eg. postgres' "2023-04-01 12:00:00+00" */
+    DateTimeTz(DateTime<FixedOffset>),
+}
+
+impl SqlValue {
+    pub fn from_cell(cell: &DataEntry) -> Result<SqlValue, Box<dyn Error + Send>> {
+        let description = match &cell.curr_field_description {
+            Some(description) => description,
+            None => return Ok(SqlValue::Text(cell.data.clone())),
+        };
+        let (sql_type, _) = split_sql_type(&description.field_type);
+        let sql_type = sql_type.trim().to_lowercase();
+
+        /* empty cells become NULL wherever the field allows it */
+        if cell.data.is_empty() && description.null == "YES" {
+            return Ok(SqlValue::Null);
+        }
+
+        let value = match sql_type.as_str() {
+            "int" | "integer" | "tinyint" | "smallint" | "mediumint" | "bigint" => cell
+                .data
+                .trim()
+                .parse()
+                .map(SqlValue::Int)
+                .map_err(|e| e.to_string()),
+            "decimal" | "numeric" => Decimal::from_str(cell.data.trim())
+                .map(SqlValue::Decimal)
+                .map_err(|e| e.to_string()),
+            "double" | "float" | "real" => cell
+                .data
+                .trim()
+                .parse()
+                .map(SqlValue::Float)
+                .map_err(|e| e.to_string()),
+            /* zero dates can't be represented by chrono, the server decides what to do with them */
+            "date" if cell.data == "0000-00-00" => Ok(SqlValue::Text(cell.data.clone())),
+            "date" => NaiveDate::parse_from_str(&cell.data, "%Y-%m-%d")
+                .map(SqlValue::Date)
+                .map_err(|e| e.to_string()),
+            "datetime" | "timestamp" if cell.data == "0000-00-00 00:00:00" => {
+                Ok(SqlValue::Text(cell.data.clone()))
+            }
+            "datetime" | "timestamp" | "timestamptz" => {
+                parse_timestamp(&cell.data).map_err(|e| e.to_string())
+            }
+            "boolean" | "bool" => parse_bool(&cell.data)
+                .map(SqlValue::Bool)
+                .ok_or_else(|| "not true or false".to_owned()),
+            _ => Ok(SqlValue::Text(cell.data.clone())),
+        };
+        value.map_err(|e| -> Box<dyn Error + Send> {
+            <String as Into<Box<dyn Error + Send + Sync>>>::into(format!(
+                "\"{}\" is not a valid {} for field \"{}\": {}",
+                cell.data, description.field_type, description.field, e
+            ))
+        })
+    }
+}
+
+/* "2023-04-01 12:00:00", optionally with fractions of a second and an offset
+ * like "+00", "+05:30" or "Z" */
+pub fn parse_timestamp(text: &str) -> Result<SqlValue, chrono::ParseError> {
+    let text = text.trim();
+    match DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f%#z") {
+        Ok(time) => Ok(SqlValue::DateTimeTz(time)),
+        Err(_) => {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").map(SqlValue::DateTime)
+        }
+    }
+}
+
+/* Takes what postgres takes for a boolean, which covers mysql's and sqlite's 1 and 0 */
+pub fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "on" | "1" => Some(true),
+        "f" | "false" | "n" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl SqlValue {
+    /* The value written out for `driver`, for scripts that run without bound parameters.
+     * mysql treats backslashes in strings as escapes unless NO_BACKSLASH_ESCAPES is set */
+    pub fn to_literal(&self, driver: DBDriver) -> String {
+        match (self, driver) {
+            (SqlValue::Text(val), DBDriver::MySql) => {
+                format!("'{}'", val.replace('\\', "\\\\").replace('\'', "''"))
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/* Used for logging and scripts, a live database always gets the values as bound parameters */
+impl fmt::Display for SqlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlValue::Null => write!(f, "NULL"),
+            SqlValue::Int(val) => write!(f, "{}", val),
+            SqlValue::Float(val) => write!(f, "{}", val),
+            SqlValue::Decimal(val) => write!(f, "{}", val),
+            SqlValue::Bool(val) => write!(f, "{}", if *val { "TRUE" } else { "FALSE" }),
+            SqlValue::Text(val) => write!(f, "'{}'", val.replace('\'', "''")),
+            SqlValue::Date(val) => write!(f, "'{}'", val.format("%Y-%m-%d")),
+            SqlValue::DateTime(val) => write!(f, "'{}'", val.format("%Y-%m-%d %H:%M:%S%.f")),
+            SqlValue::DateTimeTz(val) => {
+                write!(f, "'{}'", val.format("%Y-%m-%d %H:%M:%S%.f%:z"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SqlValue;
+    use crate::backend::csv_handler::DataEntry;
+    use crate::backend::database_handler::FieldDescription;
+    use crate::backend::db_connection::DBDriver;
+    use chrono::prelude::{NaiveDate, NaiveDateTime};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn from_cell(data: &str, field_type: &str, null: &str) -> Result<SqlValue, String> {
+        SqlValue::from_cell(&DataEntry {
+            data: data.to_owned(),
+            curr_field_description: Some(FieldDescription {
+                field: "price".to_owned(),
+                field_type: field_type.to_owned(),
+                null: null.to_owned(),
+                ..Default::default()
+            }),
+            is_parsed: None,
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn cells_convert_to_their_fields_type() {
+        assert_eq!(from_cell(" 42 ", "int(11)", "NO"), Ok(SqlValue::Int(42)));
+        assert_eq!(
+            from_cell("12.50", "decimal(15,4) unsigned", "NO"),
+            Ok(SqlValue::Decimal(Decimal::from_str("12.50").unwrap()))
+        );
+        assert_eq!(from_cell("1.5", "double", "NO"), Ok(SqlValue::Float(1.5)));
+        assert_eq!(from_cell("Yes", "boolean", "NO"), Ok(SqlValue::Bool(true)));
+        assert_eq!(
+            from_cell("2023-04-01", "date", "NO"),
+            Ok(SqlValue::Date(NaiveDate::from_ymd_opt(2023, 4, 1).unwrap()))
+        );
+        assert_eq!(
+            from_cell("0000-00-00", "date", "NO"),
+            Ok(SqlValue::Text("0000-00-00".to_owned()))
+        );
+        assert_eq!(
+            from_cell("abc", "varchar(10)", "NO"),
+            Ok(SqlValue::Text("abc".to_owned()))
+        );
+    }
+
+    #[test]
+    fn empty_cells_are_null_only_where_allowed() {
+        assert_eq!(from_cell("", "int", "YES"), Ok(SqlValue::Null));
+        assert_eq!(
+            from_cell("", "varchar(10)", "NO"),
+            Ok(SqlValue::Text(String::new()))
+        );
+        assert!(from_cell("", "int", "NO").is_err());
+    }
+
+    #[test]
+    fn invalid_cell_names_the_field() {
+        let e = from_cell("12,5", "decimal(15,4)", "NO").unwrap_err();
+        assert!(e.contains("\"12,5\" is not a valid decimal(15,4) for field \"price\""));
+        assert!(from_cell("maybe", "boolean", "NO").is_err());
+    }
+
+    #[test]
+    fn timestamps_take_fractions_and_offsets() {
+        let naive =
+            NaiveDateTime::parse_from_str("2023-04-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            from_cell("2023-04-01 12:00:00", "datetime", "NO"),
+            Ok(SqlValue::DateTime(naive))
+        );
+        for (data, utc_hour) in [
+            ("2023-04-01 12:00:00+00", 12),
+            ("2023-04-01 12:00:00+02:00", 10),
+            ("2023-04-01 12:00:00.123456-0130", 13),
+            ("2023-04-01 12:00:00Z", 12),
+        ] {
+            match from_cell(data, "timestamp", "NO") {
+                Ok(SqlValue::DateTimeTz(time)) => {
+                    assert_eq!(
+                        time.naive_utc().format("%H").to_string(),
+                        format!("{:02}", utc_hour)
+                    )
+                }
+                other => panic!("{} -> {:?}", data, other),
+            }
+        }
+        assert!(from_cell("2023-04-01 12:00", "timestamp", "NO").is_err());
+    }
+
+    #[test]
+    fn literals_are_escaped_for_the_driver() {
+        let text = SqlValue::Text("it's C:\\temp".to_owned());
+        assert_eq!(text.to_literal(DBDriver::MySql), "'it''s C:\\\\temp'");
+        assert_eq!(text.to_literal(DBDriver::Postgres), "'it''s C:\\temp'");
+        assert_eq!(text.to_literal(DBDriver::Sqlite), "'it''s C:\\temp'");
+        assert_eq!(SqlValue::Null.to_literal(DBDriver::MySql), "NULL");
+        assert_eq!(
+            SqlValue::Bool(false).to_literal(DBDriver::Postgres),
+            "FALSE"
+        );
+        assert_eq!(SqlValue::Int(-3).to_literal(DBDriver::Sqlite), "-3");
+    }
+}
@@ -0,0 +1,108 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/* `file_name` in CSQL's data directory, None without a home directory */
+pub fn data_file(file_name: &str) -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "csql").map(|dirs| dirs.data_dir().join(file_name))
+}
+/* `file_name` in CSQL's config directory, None without a home directory */
+pub fn config_file(file_name: &str) -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "csql").map(|dirs| dirs.config_dir().join(file_name))
+}
+
+/* A missing file just means nothing was saved yet, an unreadable one is logged and
+ * starts over as well. `what` names the file in the log */
+pub fn load<T: DeserializeOwned + Default>(path: Option<&Path>, what: &str) -> T {
+    let path = match path {
+        Some(path) => path,
+        None => return T::default(),
+    };
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            println!("Failed to read {} {:?}, {}", what, path, e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/* Writes a temporary file next to `path` and renames it over, so a crash halfway
+ * leaves the old file whole instead of a cut off one */
+pub fn save<T: Serialize>(
+    path: Option<&Path>,
+    what: &str,
+    value: &T,
+) -> Result<(), Box<dyn Error + Send>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            return Err(<String as Into<Box<dyn Error + Send + Sync>>>::into(
+                format!("No home directory to keep the {} in", what),
+            ))
+        }
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            return Err(Box::new(e));
+        }
+    }
+    let contents = match serde_json::to_string_pretty(value) {
+        Ok(contents) => contents,
+        Err(e) => return Err(Box::new(e)),
+    };
+    /* unique, so two saves at once don't write into the same temporary file */
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    let temp_path = path.with_file_name(temp_name);
+    if let Err(e) = std::fs::write(&temp_path, contents) {
+        return Err(Box::new(e));
+    }
+    match std::fs::rename(&temp_path, path) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            std::fs::remove_file(&temp_path).ok();
+            Err(Box::new(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save};
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("csql-test-{:016x}", rand::random::<u64>()))
+    }
+
+    #[test]
+    fn saved_file_loads_back() {
+        let dir = temp_dir();
+        let path = dir.join("queries.json");
+        let queries = vec!["SELECT 1".to_owned(), "SELECT 'it''s'".to_owned()];
+        save(Some(&path), "queries", &queries).unwrap();
+        assert_eq!(load::<Vec<String>>(Some(&path), "queries"), queries);
+        /* only the file itself is left behind */
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_or_broken_file_loads_as_default() {
+        let dir = temp_dir();
+        let path = dir.join("queries.json");
+        assert!(load::<Vec<String>>(Some(&path), "queries").is_empty());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "[\"SELECT 1\"").unwrap();
+        assert!(load::<Vec<String>>(Some(&path), "queries").is_empty());
+        assert!(load::<Vec<String>>(None, "queries").is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_without_a_home_directory_fails() {
+        assert!(save(None, "queries", &vec!["SELECT 1"]).is_err());
+    }
+}
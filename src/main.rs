@@ -6,6 +6,7 @@ use backend::backend_manager::BackendManger;
 use backend::csv_handler::ImportedData;
 use backend::database_handler::DBLoginData;
 use backend::database_handler::Tables;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
@@ -23,10 +24,12 @@ async fn main() {
         let mut backend_manager = BackendManger {
             db_login_data: DBLoginData::default(),
             imported_data: ImportedData::default(),
-            db_connection: None,
+            db_pool: None,
             csv_data,
             db_table_data,
             receiver,
+            referenced_values: HashMap::new(),
+            open_transaction: Arc::new(Mutex::new(None)),
         };
         loop {
             backend_manager.listen().await;
@@ -1,5 +1,11 @@
+pub mod add_columns_window;
+pub mod create_table_window;
 pub mod db_login_window;
 pub mod db_transaction_window;
+pub mod diff_window;
+pub mod import_journal_window;
 pub mod language;
+pub mod snapshot_window;
+pub mod sql_console_window;
 pub mod table_window;
 pub mod window_manager;
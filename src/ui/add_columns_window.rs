@@ -0,0 +1,191 @@
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::database_handler::QueryResult;
+use egui::{Context, Ui};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+type ProposedStatements = Result<Vec<String>, Box<dyn std::error::Error + Send>>;
+
+/* Adds fields to the working table for cols that aren't mapped to any,
+ * showing the exact ALTER TABLE statements before they run */
+pub struct AddColumnsWindow {
+    sender: Sender<Communication>,
+    /* (col, header) of every unmapped col */
+    unmapped_cols: Vec<(usize, String)>,
+    selected_cols: Vec<usize>,
+    statements_receiver: Option<oneshot::Receiver<ProposedStatements>>,
+    statements: Option<ProposedStatements>,
+    results_receiver: Option<oneshot::Receiver<Vec<QueryResult>>>,
+    results: Vec<QueryResult>,
+    return_status: Option<ExitStatus>,
+}
+impl AddColumnsWindow {
+    pub fn default(
+        sender: Sender<Communication>,
+        unmapped_cols: Vec<(usize, String)>,
+    ) -> AddColumnsWindow {
+        AddColumnsWindow {
+            sender,
+            unmapped_cols,
+            selected_cols: Vec::new(),
+            statements_receiver: None,
+            statements: None,
+            results_receiver: None,
+            results: Vec::new(),
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for AddColumnsWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("Add fields")
+            .id(egui::Id::new("Add fields"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 2.0,
+                frame.info().window_info.size.y / 1.5,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.poll(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl AddColumnsWindow {
+    fn poll(&mut self, ctx: &Context) {
+        if let Some(receiver) = self.statements_receiver.as_mut() {
+            if let Ok(statements) = receiver.try_recv() {
+                self.statements = Some(statements);
+                self.statements_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+        if let Some(receiver) = self.results_receiver.as_mut() {
+            if let Ok(results) = receiver.try_recv() {
+                /* the new fields are mapped by now */
+                if results.iter().all(|result| result.result.is_ok()) {
+                    self.return_status = Some(ExitStatus::Ok);
+                }
+                self.results = results;
+                self.results_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        if self.unmapped_cols.is_empty() {
+            ui.label("Every column is mapped to a field already.");
+        } else {
+            ui.label("Add a field for:");
+        }
+        egui::ScrollArea::vertical()
+            .id_source("unmapped cols")
+            .max_height(ui.available_height() / 3.0)
+            .show(ui, |ui| {
+                for (col_index, header) in self.unmapped_cols.iter() {
+                    let mut is_selected = self.selected_cols.contains(col_index);
+                    if ui.checkbox(&mut is_selected, header).changed() {
+                        if is_selected {
+                            self.selected_cols.push(*col_index);
+                        } else {
+                            self.selected_cols.retain(|selected| selected != col_index);
+                        }
+                        /* whatever was shown doesn't match the selection anymore */
+                        self.statements = None;
+                        self.results.clear();
+                    }
+                }
+            });
+        ui.separator();
+        match &self.statements {
+            Some(Ok(statements)) => {
+                ui.label("These statements will run, each one commits on its own:");
+                for statement in statements {
+                    ui.monospace(statement);
+                }
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+            }
+            None => {}
+        }
+        for result in self.results.iter() {
+            match &result.result {
+                Ok(_) => ui.colored_label(
+                    egui::Color32::LIGHT_GREEN,
+                    format!("{}\nSuccess!", result.query),
+                ),
+                Err(e) => ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!("{}\nError! {}", result.query, e),
+                ),
+            };
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.return_status = Some(ExitStatus::Ok);
+            }
+            let is_waiting = self.statements_receiver.is_some() || self.results_receiver.is_some();
+            if ui
+                .add_enabled(
+                    !self.selected_cols.is_empty() && !is_waiting,
+                    egui::Button::new("Show statements"),
+                )
+                .clicked()
+            {
+                let (sender, receiver) = oneshot::channel();
+                self.statements_receiver = Some(receiver);
+                self.sender
+                    .try_send(Communication::ProposeColumns(
+                        self.selected_cols.clone(),
+                        sender,
+                    ))
+                    .unwrap_or_else(|_| println!("Failed to send ProposeColumns"));
+            }
+            let statements = match &self.statements {
+                Some(Ok(statements)) if !statements.is_empty() => Some(statements.clone()),
+                _ => None,
+            };
+            if ui
+                .add_enabled(
+                    statements.is_some() && !is_waiting,
+                    egui::Button::new("Run them"),
+                )
+                .clicked()
+            {
+                let (sender, receiver) = oneshot::channel();
+                self.results_receiver = Some(receiver);
+                self.sender
+                    .try_send(Communication::AddColumns(
+                        statements.unwrap(),
+                        self.selected_cols.clone(),
+                        sender,
+                    ))
+                    .unwrap_or_else(|_| println!("Failed to send AddColumns"));
+            }
+            if is_waiting {
+                ui.spinner();
+            }
+        });
+    }
+}
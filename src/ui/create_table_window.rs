@@ -0,0 +1,137 @@
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::database_handler::QueryResult;
+use egui::{Context, Ui};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+/* Proposes a CREATE TABLE for the spreadsheet, which can be edited before it runs */
+pub struct CreateTableWindow {
+    sender: Sender<Communication>,
+    table_name: String,
+    statement: String,
+    statement_receiver: Option<oneshot::Receiver<String>>,
+    is_statement_requested: bool,
+    result_receiver: Option<oneshot::Receiver<QueryResult>>,
+    last_result: Option<QueryResult>,
+    return_status: Option<ExitStatus>,
+}
+impl CreateTableWindow {
+    pub fn default(sender: Sender<Communication>, table_name: String) -> CreateTableWindow {
+        CreateTableWindow {
+            sender,
+            table_name,
+            statement: String::new(),
+            statement_receiver: None,
+            is_statement_requested: false,
+            result_receiver: None,
+            last_result: None,
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for CreateTableWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("Create table")
+            .id(egui::Id::new("Create table"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 2.0,
+                frame.info().window_info.size.y / 1.5,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.poll(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl CreateTableWindow {
+    fn poll(&mut self, ctx: &Context) {
+        if !self.is_statement_requested {
+            self.is_statement_requested = true;
+            let (sender, receiver) = oneshot::channel();
+            self.statement_receiver = Some(receiver);
+            self.sender
+                .try_send(Communication::ProposeTable(self.table_name.clone(), sender))
+                .unwrap_or_else(|_| println!("Failed to send ProposeTable"));
+        }
+        if let Some(receiver) = self.statement_receiver.as_mut() {
+            if let Ok(statement) = receiver.try_recv() {
+                self.statement = statement;
+                self.statement_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+        if let Some(receiver) = self.result_receiver.as_mut() {
+            if let Ok(result) = receiver.try_recv() {
+                /* the new table is selected and mapped by now */
+                if result.result.is_ok() {
+                    self.return_status = Some(ExitStatus::Ok);
+                }
+                self.last_result = Some(result);
+                self.result_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Table name:");
+            ui.text_edit_singleline(&mut self.table_name);
+            if ui.button("Propose again").clicked() {
+                self.is_statement_requested = false;
+            }
+        });
+        ui.label("Types are guessed from the spreadsheet, check them before creating the table:");
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 60.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.statement)
+                        .code_editor()
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        if let Some(Err(e)) = self.last_result.as_ref().map(|result| &result.result) {
+            ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.return_status = Some(ExitStatus::Ok);
+            }
+            let is_ready = !self.statement.trim().is_empty() && self.result_receiver.is_none();
+            if ui
+                .add_enabled(is_ready, egui::Button::new("Create"))
+                .clicked()
+            {
+                let (sender, receiver) = oneshot::channel();
+                self.result_receiver = Some(receiver);
+                self.sender
+                    .try_send(Communication::CreateTable(self.statement.clone(), sender))
+                    .unwrap_or_else(|_| println!("Failed to send CreateTable"));
+            }
+            if self.result_receiver.is_some() {
+                ui.spinner();
+            }
+        });
+    }
+}
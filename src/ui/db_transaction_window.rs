@@ -1,31 +1,59 @@
 use egui::{Context, Ui};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, watch};
 
 use crate::backend::backend_manager::Communication;
-use crate::backend::database_handler::QueryResult;
+use crate::backend::database_handler::{
+    ErrorPolicy, InsertOptions, QueryResult, RowIssue, MAX_INSERT_WORKERS,
+};
 
 use super::window_manager::{CSQLWindow, ExitStatus};
 
 pub struct DBTransactionWindow {
     sender: Sender<Communication>,
+    transaction_type: TransactionType,
     log_history: Vec<QueryResult>,
     logs_receiver: Option<Receiver<QueryResult>>,
-    is_log_finished_receiver: Option<oneshot::Receiver<bool>>,
+    progress_receiver: Option<watch::Receiver<(usize, usize)>>,
+    is_log_finished_receiver: Option<oneshot::Receiver<Vec<usize>>>,
     is_log_finished: bool,
+    error_policy: ErrorPolicy,
+    max_errors: usize,
+    options: OptionsDBTransactionWindow,
+    /* connections appending at once, with more than one every batch commits right away */
+    workers: usize,
+    is_started: bool,
+    /* grid rows that failed or weren't attempted */
+    skipped_rows: Vec<usize>,
     is_finished: bool,
     final_result_receiver: Option<oneshot::Receiver<QueryResult>>,
+    test_report_receiver: Option<oneshot::Receiver<TestReport>>,
+    test_report: Option<TestReport>,
 }
+type TestReport = Result<Vec<RowIssue>, Box<dyn std::error::Error + Send>>;
 impl DBTransactionWindow {
-    pub fn default(sender: Sender<Communication>) -> DBTransactionWindow {
+    pub fn default(
+        sender: Sender<Communication>,
+        transaction_type: TransactionType,
+    ) -> DBTransactionWindow {
         DBTransactionWindow {
             sender,
+            transaction_type,
             log_history: vec![],
             is_finished: false,
             logs_receiver: None,
+            progress_receiver: None,
             final_result_receiver: None,
             is_log_finished_receiver: None,
             is_log_finished: false,
+            error_policy: ErrorPolicy::SkipFailedRows,
+            max_errors: 10,
+            options: OptionsDBTransactionWindow::default(),
+            workers: 1,
+            is_started: false,
+            skipped_rows: vec![],
+            test_report_receiver: None,
+            test_report: None,
         }
     }
 }
@@ -51,8 +79,12 @@ impl CSQLWindow for DBTransactionWindow {
             ))
             .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
             .show(ctx, |ui| {
-                self.log();
-                self.ui(ctx, ui, frame);
+                if self.is_started {
+                    self.log();
+                    self.ui(ctx, ui, frame);
+                } else {
+                    self.policy_ui(ui);
+                }
             });
 
         if self.is_finished {
@@ -71,30 +103,175 @@ impl DBTransactionWindow {
             }
         }
         if self.logs_receiver.is_some() {
-            if let Ok(log) = self.logs_receiver.as_mut().unwrap().try_recv() {
+            while let Ok(log) = self.logs_receiver.as_mut().unwrap().try_recv() {
                 self.log_history.push(log);
             }
         }
         if self.is_log_finished_receiver.is_some() {
-            if let Ok(finished) = self.is_log_finished_receiver.as_mut().unwrap().try_recv() {
-                self.is_log_finished = finished;
+            if let Ok(skipped_rows) = self.is_log_finished_receiver.as_mut().unwrap().try_recv() {
+                self.skipped_rows = skipped_rows;
+                self.is_log_finished = true;
                 println!("FINISHED QUERYING!!!");
             }
         }
+        if let Some(report_receiver) = self.test_report_receiver.as_mut() {
+            if let Ok(report) = report_receiver.try_recv() {
+                self.test_report = Some(report);
+                self.test_report_receiver = None;
+                self.is_log_finished = true;
+            }
+        }
         if self.is_log_finished_receiver.is_none() && self.logs_receiver.is_none() {
             let (log_sender, log_receiver) = channel(2);
+            let (progress_sender, progress_receiver) = watch::channel((0, 0));
             let (finished_sender, finished_receiver) = oneshot::channel();
             self.is_log_finished_receiver = Some(finished_receiver);
             self.logs_receiver = Some(log_receiver);
+            self.progress_receiver = Some(progress_receiver);
+            let command = match &self.transaction_type {
+                TransactionType::Append => Communication::StartAppending(
+                    self.error_policy,
+                    self.options.insert_options(),
+                    self.workers,
+                    log_sender,
+                    progress_sender,
+                    finished_sender,
+                ),
+                TransactionType::AppendToTables => Communication::StartAppendingToTables(
+                    self.error_policy,
+                    self.options.insert_options(),
+                    log_sender,
+                    progress_sender,
+                    finished_sender,
+                ),
+                TransactionType::Save => Communication::StartInserting(
+                    self.error_policy,
+                    self.options.insert_options(),
+                    log_sender,
+                    progress_sender,
+                    finished_sender,
+                ),
+                TransactionType::Upsert(key_fields) => Communication::StartUpserting(
+                    key_fields.clone(),
+                    self.error_policy,
+                    self.options.insert_options(),
+                    log_sender,
+                    progress_sender,
+                    finished_sender,
+                ),
+                /* reports back through its own channel, there are no rows left to skip */
+                TransactionType::TestImport => {
+                    let (report_sender, report_receiver) = oneshot::channel();
+                    self.test_report_receiver = Some(report_receiver);
+                    Communication::StartTestImport(
+                        self.options.insert_options(),
+                        log_sender,
+                        progress_sender,
+                        report_sender,
+                    )
+                }
+            };
             self.sender
-                .try_send(Communication::StartAppending(log_sender, finished_sender))
+                .try_send(command)
                 .unwrap_or_else(|_| println!("Failed to send startInserting"));
         }
     }
+    /* Asked once before anything gets sent to the database */
+    fn policy_ui(&mut self, ui: &mut Ui) {
+        if let TransactionType::TestImport = self.transaction_type {
+            ui.label("Every row gets inserted on its own, then all of it is rolled back.");
+            ui.separator();
+            self.options.ui(ui);
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Cancel").clicked() {
+                    self.is_finished = true;
+                }
+                if ui.button("Start").clicked() {
+                    self.is_started = true;
+                }
+            });
+            return;
+        }
+        ui.label("When a row fails:");
+        ui.radio_value(
+            &mut self.error_policy,
+            ErrorPolicy::SkipFailedRows,
+            "Skip it and keep going",
+        );
+        ui.radio_value(
+            &mut self.error_policy,
+            ErrorPolicy::AbortOnFirstError,
+            "Stop right away",
+        );
+        ui.horizontal(|ui| {
+            let is_selected = matches!(self.error_policy, ErrorPolicy::AbortAfterErrors(_));
+            if ui.radio(is_selected, "Stop after").clicked() || is_selected {
+                self.error_policy = ErrorPolicy::AbortAfterErrors(self.max_errors);
+            }
+            if ui
+                .add(egui::DragValue::new(&mut self.max_errors).clamp_range(1..=usize::MAX))
+                .changed()
+                && is_selected
+            {
+                self.error_policy = ErrorPolicy::AbortAfterErrors(self.max_errors);
+            }
+            ui.label("failed rows");
+        });
+        ui.separator();
+        self.options.ui(ui);
+        if let TransactionType::Append = self.transaction_type {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Parallel connections:");
+                ui.add(egui::DragValue::new(&mut self.workers).clamp_range(1..=MAX_INSERT_WORKERS));
+            });
+            if self.workers > 1 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Rows are committed as they go in, there will be nothing to roll back.",
+                );
+            }
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Cancel").clicked() {
+                self.is_finished = true;
+            }
+            if ui.button("Start").clicked() {
+                self.is_started = true;
+            }
+        });
+    }
+    /* parallel appends don't run in a transaction, tests roll theirs back by themselves */
+    fn is_committed_as_it_goes(&self) -> bool {
+        matches!(self.transaction_type, TransactionType::Append) && self.workers > 1
+            || matches!(self.transaction_type, TransactionType::TestImport)
+    }
     pub fn ui(&mut self, ctx: &Context, ui: &mut Ui, frame: &mut eframe::Frame) {
+        if let Some(progress_receiver) = &self.progress_receiver {
+            let (inserted_rows, total_rows) = *progress_receiver.borrow();
+            let progress = if total_rows == 0 {
+                0.0
+            } else {
+                inserted_rows as f32 / total_rows as f32
+            };
+            ui.add(
+                egui::ProgressBar::new(progress)
+                    .text(format!("{}/{} rows", inserted_rows, total_rows)),
+            );
+        }
+        /* keep polling the backend while it's still inserting */
+        if !self.is_log_finished {
+            ctx.request_repaint();
+        }
         ui.add_enabled_ui(self.is_log_finished, |ui| {
             ui.horizontal(|ui| {
-                if ui.button("Roll back").clicked() {
+                if self.is_committed_as_it_goes() {
+                    if ui.button("Close").clicked() {
+                        self.is_finished = true;
+                    }
+                } else if ui.button("Roll back").clicked() {
                     if self.final_result_receiver.is_none() {
                         let (sender, receiver) = oneshot::channel();
                         self.final_result_receiver = Some(receiver);
@@ -103,7 +280,7 @@ impl DBTransactionWindow {
                             .unwrap_or_else(|_| println!("failed sending TryCommit receiver"));
                     }
                 }
-                if ui.button("Commit").clicked() {
+                if !self.is_committed_as_it_goes() && ui.button("Commit").clicked() {
                     if self.final_result_receiver.is_none() {
                         let (sender, receiver) = oneshot::channel();
                         self.final_result_receiver = Some(receiver);
@@ -112,8 +289,22 @@ impl DBTransactionWindow {
                             .unwrap_or_else(|_| println!("failed sending TryCommit receiver"));
                     }
                 }
+                if !self.skipped_rows.is_empty() {
+                    ui.label(format!("{} rows not inserted", self.skipped_rows.len()));
+                    /* lets the failed rows get fixed and sent again in a new transaction */
+                    if ui
+                        .button("Keep only these rows in the spreadsheet")
+                        .clicked()
+                    {
+                        self.sender
+                            .try_send(Communication::KeepOnlyRows(self.skipped_rows.clone()))
+                            .unwrap_or_else(|_| println!("failed sending KeepOnlyRows"));
+                        self.skipped_rows.clear();
+                    }
+                }
             });
         });
+        self.test_report_ui(ui);
         /* DB Output Stuff */
         egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
@@ -176,13 +367,116 @@ impl DBTransactionWindow {
     }
 }
 
+impl DBTransactionWindow {
+    /* What the server said per grid row, once a test import is done */
+    fn test_report_ui(&mut self, ui: &mut Ui) {
+        let issues = match &self.test_report {
+            Some(Ok(issues)) => issues,
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Test failed, {}", e));
+                return;
+            }
+            None => return,
+        };
+        let error_count = issues.iter().filter(|issue| issue.is_error).count();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} errors and {} warnings, nothing was kept",
+                error_count,
+                issues.len() - error_count
+            ));
+            /* the ones that name a mapped field show up on their cell */
+            if ui
+                .add_enabled(
+                    issues.iter().any(|issue| issue.col.is_some()),
+                    egui::Button::new("Mark on the spreadsheet"),
+                )
+                .clicked()
+            {
+                self.sender
+                    .try_send(Communication::MarkRowIssues(issues.clone()))
+                    .unwrap_or_else(|_| println!("failed sending MarkRowIssues"));
+            }
+        });
+        if issues.is_empty() {
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_source("test report")
+            .max_height(ui.available_height() / 2.0)
+            .show(ui, |ui| {
+                egui::Grid::new("test report grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Row");
+                        ui.strong("Field");
+                        ui.strong("Message");
+                        ui.end_row();
+                        for issue in issues {
+                            ui.label(issue.row.to_string());
+                            ui.label(match &issue.field {
+                                Some(field) => format!("{}.{}", issue.table, field),
+                                None => issue.table.clone(),
+                            });
+                            let color = if issue.is_error {
+                                egui::Color32::LIGHT_RED
+                            } else {
+                                egui::Color32::YELLOW
+                            };
+                            ui.colored_label(color, &issue.message);
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.separator();
+    }
+}
+
+/* How the rows get written, asked along with the error policy */
 pub struct OptionsDBTransactionWindow {
     substitute_zero_dates_for_null: bool,
     remove_id_field_from_insert: bool,
+    use_defaults_for_empty_cells: bool,
+}
+impl OptionsDBTransactionWindow {
+    pub fn default() -> OptionsDBTransactionWindow {
+        OptionsDBTransactionWindow {
+            substitute_zero_dates_for_null: false,
+            remove_id_field_from_insert: false,
+            use_defaults_for_empty_cells: false,
+        }
+    }
+    pub fn insert_options(&self) -> InsertOptions {
+        InsertOptions {
+            skip_auto_increment: self.remove_id_field_from_insert,
+            zero_dates_to_null: self.substitute_zero_dates_for_null,
+            empty_cells_to_default: self.use_defaults_for_empty_cells,
+        }
+    }
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(
+            &mut self.remove_id_field_from_insert,
+            "Leave out auto_increment fields, the database numbers the rows",
+        );
+        ui.checkbox(
+            &mut self.substitute_zero_dates_for_null,
+            "Insert 0000-00-00 dates as NULL where the field allows it",
+        );
+        ui.checkbox(
+            &mut self.use_defaults_for_empty_cells,
+            "Use the field's default for empty cells",
+        );
+    }
 }
 
+#[derive(Clone)]
 pub enum TransactionType {
     Append,
+    /* Append into the working table and every linked one, a row at a time */
+    AppendToTables,
     Save,
-    Import,
+    /* Updates rows that already have the same values in these fields, inserts the rest */
+    Upsert(Vec<String>),
+    /* Inserts everything to see what the server makes of it, then rolls back */
+    TestImport,
 }
@@ -0,0 +1,196 @@
+use super::db_transaction_window::TransactionType;
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::diff::{RowStatus, TableDiff};
+use egui::{Context, Ui};
+use egui_extras::{Column, TableBuilder};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+pub struct DiffWindow {
+    sender: Sender<Communication>,
+    key_field: String,
+    diff_receiver: Option<oneshot::Receiver<Result<TableDiff, Box<dyn std::error::Error + Send>>>>,
+    diff: Option<Result<TableDiff, Box<dyn std::error::Error + Send>>>,
+    show_new: bool,
+    show_changed: bool,
+    show_unchanged: bool,
+    return_status: Option<ExitStatus>,
+}
+impl DiffWindow {
+    pub fn default(sender: Sender<Communication>, key_field: String) -> DiffWindow {
+        DiffWindow {
+            sender,
+            key_field,
+            diff_receiver: None,
+            diff: None,
+            show_new: true,
+            show_changed: true,
+            show_unchanged: false,
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for DiffWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("Compare with database")
+            .id(egui::Id::new("Compare with database"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 1.3,
+                frame.info().window_info.size.y / 1.5,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.request_diff(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl DiffWindow {
+    fn request_diff(&mut self, ctx: &Context) {
+        if self.diff_receiver.is_none() && self.diff.is_none() {
+            let (sender, receiver) = oneshot::channel();
+            self.diff_receiver = Some(receiver);
+            self.sender
+                .try_send(Communication::DiffWithTable(self.key_field.clone(), sender))
+                .unwrap_or_else(|_| println!("Failed to send DiffWithTable"));
+        }
+        if let Some(receiver) = self.diff_receiver.as_mut() {
+            if let Ok(diff) = receiver.try_recv() {
+                self.diff = Some(diff);
+                self.diff_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.return_status = Some(ExitStatus::Ok);
+            }
+            ui.add_enabled_ui(matches!(self.diff, Some(Ok(_))), |ui| {
+                if ui
+                    .button(format!("Upsert on \"{}\"", self.key_field))
+                    .clicked()
+                {
+                    self.return_status = Some(ExitStatus::StartTransactionWindow(
+                        TransactionType::Upsert(vec![self.key_field.clone()]),
+                    ));
+                }
+            });
+        });
+        ui.separator();
+
+        let diff = match &self.diff {
+            Some(Ok(diff)) => diff,
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+                return;
+            }
+            None => {
+                ui.spinner();
+                return;
+            }
+        };
+        let (new_count, changed_count, unchanged_count) = diff.counts();
+        ui.horizontal(|ui| {
+            ui.label(format!("Matched on \"{}\":", diff.key_field));
+            ui.checkbox(&mut self.show_new, format!("{} new", new_count));
+            ui.checkbox(&mut self.show_changed, format!("{} changed", changed_count));
+            ui.checkbox(
+                &mut self.show_unchanged,
+                format!("{} unchanged", unchanged_count),
+            );
+        });
+
+        let shown_rows: Vec<_> = diff
+            .rows
+            .iter()
+            .filter(|row| match row.status {
+                RowStatus::New => self.show_new,
+                RowStatus::Changed(_) => self.show_changed,
+                RowStatus::Unchanged => self.show_unchanged,
+            })
+            .collect();
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::auto().clip(true))
+            .column(Column::auto())
+            .column(Column::remainder().clip(true))
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Row");
+                });
+                header.col(|ui| {
+                    ui.strong(&diff.key_field);
+                });
+                header.col(|ui| {
+                    ui.strong("Status");
+                });
+                header.col(|ui| {
+                    ui.strong("Changes");
+                });
+            })
+            .body(|mut body| {
+                for row_diff in shown_rows {
+                    let change_count = match &row_diff.status {
+                        RowStatus::Changed(changes) => changes.len(),
+                        _ => 1,
+                    };
+                    body.row(18.0 * change_count as f32, |mut row| {
+                        row.col(|ui| {
+                            ui.label(row_diff.row_index.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(&row_diff.key);
+                        });
+                        row.col(|ui| match &row_diff.status {
+                            RowStatus::New => {
+                                ui.colored_label(egui::Color32::LIGHT_GREEN, "New");
+                            }
+                            RowStatus::Changed(_) => {
+                                ui.colored_label(egui::Color32::YELLOW, "Changed");
+                            }
+                            RowStatus::Unchanged => {
+                                ui.label("Unchanged");
+                            }
+                        });
+                        row.col(|ui| {
+                            if let RowStatus::Changed(changes) = &row_diff.status {
+                                ui.vertical(|ui| {
+                                    for change in changes {
+                                        ui.label(format!(
+                                            "{}: {} → {}",
+                                            change.field,
+                                            change.before.as_deref().unwrap_or("NULL"),
+                                            change.after
+                                        ));
+                                    }
+                                });
+                            }
+                        });
+                    });
+                }
+            });
+    }
+}
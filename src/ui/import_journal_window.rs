@@ -0,0 +1,245 @@
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::database_handler::QueryResult;
+use crate::backend::journal::ImportRecord;
+use egui::{Context, Ui};
+use egui_extras::{Column, TableBuilder};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+type UndoCheck = Result<(usize, usize), Box<dyn std::error::Error + Send>>;
+
+pub struct ImportJournalWindow {
+    sender: Sender<Communication>,
+    records_receiver: Option<oneshot::Receiver<Vec<ImportRecord>>>,
+    records: Option<Vec<ImportRecord>>,
+    /* the import waiting for its undo to be confirmed */
+    undo_candidate: Option<u64>,
+    check_receiver: Option<oneshot::Receiver<UndoCheck>>,
+    check: Option<UndoCheck>,
+    undo_receiver: Option<oneshot::Receiver<QueryResult>>,
+    last_undo: Option<QueryResult>,
+    return_status: Option<ExitStatus>,
+}
+impl ImportJournalWindow {
+    pub fn default(sender: Sender<Communication>) -> ImportJournalWindow {
+        ImportJournalWindow {
+            sender,
+            records_receiver: None,
+            records: None,
+            undo_candidate: None,
+            check_receiver: None,
+            check: None,
+            undo_receiver: None,
+            last_undo: None,
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for ImportJournalWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("Past imports")
+            .id(egui::Id::new("Past imports"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 2.0,
+                frame.info().window_info.size.y / 2.0,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.poll(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl ImportJournalWindow {
+    fn poll(&mut self, ctx: &Context) {
+        if let Some(receiver) = self.undo_receiver.as_mut() {
+            if let Ok(result) = receiver.try_recv() {
+                self.last_undo = Some(result);
+                self.undo_receiver = None;
+                self.records = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+        if let Some(receiver) = self.check_receiver.as_mut() {
+            if let Ok(check) = receiver.try_recv() {
+                self.check = Some(check);
+                self.check_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+        if self.records_receiver.is_none() && self.records.is_none() {
+            let (sender, receiver) = oneshot::channel();
+            self.records_receiver = Some(receiver);
+            self.sender
+                .try_send(Communication::ListImports(sender))
+                .unwrap_or_else(|_| println!("Failed to send ListImports"));
+        }
+        if let Some(receiver) = self.records_receiver.as_mut() {
+            if let Ok(records) = receiver.try_recv() {
+                self.records = Some(records);
+                self.records_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        if ui.button("Close").clicked() {
+            self.return_status = Some(ExitStatus::Ok);
+        }
+        if let Some(last_undo) = &self.last_undo {
+            match &last_undo.result {
+                Ok(res) => ui.colored_label(
+                    egui::Color32::LIGHT_GREEN,
+                    format!(
+                        "{}\nSuccess! Rows affected: {}",
+                        last_undo.query,
+                        res.rows_affected()
+                    ),
+                ),
+                Err(e) => ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!("{}\nError! {}", last_undo.query, e),
+                ),
+            };
+        }
+        if let Some(id) = self.undo_candidate {
+            self.confirm_ui(ui, id);
+        }
+        ui.separator();
+
+        let records = match &self.records {
+            Some(records) => records.clone(),
+            None => {
+                ui.spinner();
+                return;
+            }
+        };
+        if records.is_empty() {
+            ui.label("No committed appends to this database yet.");
+            return;
+        }
+        let is_busy = self.undo_candidate.is_some() || self.undo_receiver.is_some();
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::remainder().clip(true))
+            .column(Column::auto())
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Committed");
+                });
+                header.col(|ui| {
+                    ui.strong("Table");
+                });
+                header.col(|ui| {
+                    ui.strong("Rows");
+                });
+                header.col(|_| {});
+            })
+            .body(|mut body| {
+                for record in records.iter() {
+                    body.row(20.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(&record.time);
+                        });
+                        row.col(|ui| {
+                            ui.label(&record.table);
+                        });
+                        row.col(|ui| {
+                            ui.label(record.rows.len().to_string());
+                        });
+                        row.col(|ui| {
+                            if record.is_undone {
+                                ui.label("Undone");
+                                return;
+                            }
+                            ui.add_enabled_ui(!is_busy, |ui| {
+                                if ui.button("Undo").clicked() {
+                                    let (sender, receiver) = oneshot::channel();
+                                    self.undo_candidate = Some(record.id);
+                                    self.check = None;
+                                    self.check_receiver = Some(receiver);
+                                    self.sender
+                                        .try_send(Communication::CheckImportUndo(record.id, sender))
+                                        .unwrap_or_else(|_| {
+                                            println!("Failed to send CheckImportUndo")
+                                        });
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+    }
+    /* Undoing deletes rows, so say what changed since the import before going ahead */
+    fn confirm_ui(&mut self, ui: &mut Ui, id: u64) {
+        let row_count = self
+            .records
+            .iter()
+            .flatten()
+            .find(|record| record.id == id)
+            .map_or(0, |record| record.rows.len());
+        match &self.check {
+            None => {
+                ui.spinner();
+                return;
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+            }
+            Some(Ok((0, 0))) => {
+                ui.label(format!(
+                    "All {} rows are as they were imported and will be deleted.",
+                    row_count
+                ));
+            }
+            Some(Ok((modified, missing))) => {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "{} of the {} rows were changed since the import and {} are already gone. \
+                         Undoing deletes the changed rows too.",
+                        modified, row_count, missing
+                    ),
+                );
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Cancel").clicked() {
+                self.undo_candidate = None;
+                self.check = None;
+            }
+            if matches!(self.check, Some(Ok(_))) && ui.button("Undo import").clicked() {
+                let (sender, receiver) = oneshot::channel();
+                self.undo_receiver = Some(receiver);
+                self.sender
+                    .try_send(Communication::UndoImport(id, sender))
+                    .unwrap_or_else(|_| println!("Failed to send UndoImport"));
+                self.undo_candidate = None;
+                self.check = None;
+            }
+        });
+    }
+}
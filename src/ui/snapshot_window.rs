@@ -0,0 +1,232 @@
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::database_handler::QueryResult;
+use chrono::NaiveDateTime;
+use egui::{Context, Ui};
+use egui_extras::{Column, TableBuilder};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+type SnapshotList = Result<Vec<String>, Box<dyn std::error::Error + Send>>;
+
+#[derive(Clone)]
+enum SnapshotAction {
+    Restore,
+    Delete,
+}
+
+pub struct SnapshotWindow {
+    sender: Sender<Communication>,
+    snapshots_receiver: Option<oneshot::Receiver<SnapshotList>>,
+    snapshots: Option<SnapshotList>,
+    /* the snapshot waiting for its restore or delete to be confirmed */
+    action_candidate: Option<(SnapshotAction, String)>,
+    action_receiver: Option<oneshot::Receiver<QueryResult>>,
+    last_action: Option<QueryResult>,
+    return_status: Option<ExitStatus>,
+}
+impl SnapshotWindow {
+    pub fn default(sender: Sender<Communication>) -> SnapshotWindow {
+        SnapshotWindow {
+            sender,
+            snapshots_receiver: None,
+            snapshots: None,
+            action_candidate: None,
+            action_receiver: None,
+            last_action: None,
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for SnapshotWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("Table snapshots")
+            .id(egui::Id::new("Table snapshots"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 2.0,
+                frame.info().window_info.size.y / 2.0,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.poll(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl SnapshotWindow {
+    fn poll(&mut self, ctx: &Context) {
+        if let Some(receiver) = self.action_receiver.as_mut() {
+            if let Ok(result) = receiver.try_recv() {
+                self.last_action = Some(result);
+                self.action_receiver = None;
+                /* the list changes after a delete, reload it either way */
+                self.snapshots = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+        if self.snapshots_receiver.is_none() && self.snapshots.is_none() {
+            let (sender, receiver) = oneshot::channel();
+            self.snapshots_receiver = Some(receiver);
+            self.sender
+                .try_send(Communication::ListSnapshots(sender))
+                .unwrap_or_else(|_| println!("Failed to send ListSnapshots"));
+        }
+        if let Some(receiver) = self.snapshots_receiver.as_mut() {
+            if let Ok(snapshots) = receiver.try_recv() {
+                self.snapshots = Some(snapshots);
+                self.snapshots_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        if ui.button("Close").clicked() {
+            self.return_status = Some(ExitStatus::Ok);
+        }
+        if let Some(last_action) = &self.last_action {
+            match &last_action.result {
+                Ok(res) => ui.colored_label(
+                    egui::Color32::LIGHT_GREEN,
+                    format!(
+                        "{}\nSuccess! Rows affected: {}",
+                        last_action.query,
+                        res.rows_affected()
+                    ),
+                ),
+                Err(e) => ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!("{}\nError! {}", last_action.query, e),
+                ),
+            };
+        }
+        if let Some((action, snapshot)) = self.action_candidate.clone() {
+            self.confirm_ui(ui, action, snapshot);
+        }
+        ui.separator();
+
+        let snapshots = match &self.snapshots {
+            Some(Ok(snapshots)) => snapshots.clone(),
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+                return;
+            }
+            None => {
+                ui.spinner();
+                return;
+            }
+        };
+        if snapshots.is_empty() {
+            ui.label("No snapshots yet, one is taken before every save or upsert to the table.");
+            return;
+        }
+        let is_busy = self.action_candidate.is_some() || self.action_receiver.is_some();
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::remainder().clip(true))
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Taken");
+                });
+                header.col(|ui| {
+                    ui.strong("Table");
+                });
+                header.col(|_| {});
+            })
+            .body(|mut body| {
+                for snapshot in snapshots.iter() {
+                    body.row(20.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(snapshot_time(snapshot));
+                        });
+                        row.col(|ui| {
+                            ui.label(snapshot);
+                        });
+                        row.col(|ui| {
+                            ui.add_enabled_ui(!is_busy, |ui| {
+                                if ui.button("Restore").clicked() {
+                                    self.action_candidate =
+                                        Some((SnapshotAction::Restore, snapshot.clone()));
+                                }
+                                if ui.button("Delete").clicked() {
+                                    self.action_candidate =
+                                        Some((SnapshotAction::Delete, snapshot.clone()));
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+    }
+    /* Both replace something for good, so ask before going ahead */
+    fn confirm_ui(&mut self, ui: &mut Ui, action: SnapshotAction, snapshot: String) {
+        let time = snapshot_time(&snapshot);
+        match action {
+            SnapshotAction::Restore => ui.colored_label(
+                egui::Color32::YELLOW,
+                format!(
+                    "Every row of the table will be replaced with the ones from {}. \
+                     The table is snapshotted first, so this can be restored back.",
+                    time
+                ),
+            ),
+            SnapshotAction::Delete => ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("The snapshot from {} will be dropped for good.", time),
+            ),
+        };
+        ui.horizontal(|ui| {
+            if ui.button("Cancel").clicked() {
+                self.action_candidate = None;
+            }
+            let label = match action {
+                SnapshotAction::Restore => "Restore snapshot",
+                SnapshotAction::Delete => "Delete snapshot",
+            };
+            if ui.button(label).clicked() {
+                let (sender, receiver) = oneshot::channel();
+                self.action_receiver = Some(receiver);
+                let communication = match action {
+                    SnapshotAction::Restore => Communication::RestoreSnapshot(snapshot, sender),
+                    SnapshotAction::Delete => Communication::DeleteSnapshot(snapshot, sender),
+                };
+                self.sender
+                    .try_send(communication)
+                    .unwrap_or_else(|_| println!("Failed to send the snapshot action"));
+                self.action_candidate = None;
+            }
+        });
+    }
+}
+
+/* "csql_snapshot_oc_product_20230401120000" -> "2023-04-01 12:00:00" */
+fn snapshot_time(snapshot: &str) -> String {
+    snapshot
+        .rsplit('_')
+        .next()
+        .and_then(|timestamp| NaiveDateTime::parse_from_str(timestamp, "%Y%m%d%H%M%S").ok())
+        .map_or_else(
+            || snapshot.to_owned(),
+            |time| time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        )
+}
@@ -0,0 +1,216 @@
+use super::window_manager::{CSQLWindow, ExitStatus};
+use crate::backend::backend_manager::Communication;
+use crate::backend::database_handler::ConsoleOutput;
+use crate::backend::query_history::QueryHistory;
+use egui::{Context, Ui};
+use egui_extras::{Column, TableBuilder};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+
+type QueryOutput = Result<ConsoleOutput, Box<dyn std::error::Error + Send>>;
+
+/* Runs any SQL on the database CSQL is connected to, without leaving the import */
+pub struct SQLConsoleWindow {
+    sender: Sender<Communication>,
+    query: String,
+    history: QueryHistory,
+    /* run on the connection holding the import's uncommitted rows */
+    in_open_transaction: bool,
+    output_receiver: Option<oneshot::Receiver<QueryOutput>>,
+    output: Option<QueryOutput>,
+    return_status: Option<ExitStatus>,
+}
+impl SQLConsoleWindow {
+    pub fn default(sender: Sender<Communication>) -> SQLConsoleWindow {
+        let history = QueryHistory::load();
+        SQLConsoleWindow {
+            sender,
+            query: history.queries.last().cloned().unwrap_or_default(),
+            history,
+            in_open_transaction: false,
+            output_receiver: None,
+            output: None,
+            return_status: None,
+        }
+    }
+}
+impl CSQLWindow for SQLConsoleWindow {
+    fn refresh(
+        &mut self,
+        ctx: &Context,
+        _ui: &mut Ui,
+        frame: &mut eframe::Frame,
+    ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>> {
+        egui::Window::new("SQL console")
+            .id(egui::Id::new("SQL console"))
+            .resizable(false)
+            .collapsible(true)
+            .title_bar(true)
+            .movable(false)
+            .enabled(true)
+            .fixed_size(egui::Vec2::new(
+                frame.info().window_info.size.x / 1.3,
+                frame.info().window_info.size.y / 1.5,
+            ))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                self.poll(ctx);
+                self.ui(ui);
+            });
+
+        if let Some(return_status) = self.return_status.clone() {
+            self.return_status = None;
+            return Some(Ok(return_status));
+        }
+        None
+    }
+}
+impl SQLConsoleWindow {
+    fn poll(&mut self, ctx: &Context) {
+        if let Some(receiver) = self.output_receiver.as_mut() {
+            if let Ok(output) = receiver.try_recv() {
+                self.output = Some(output);
+                self.output_receiver = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+    fn run(&mut self) {
+        if self.query.trim().is_empty() || self.output_receiver.is_some() {
+            return;
+        }
+        self.history
+            .add(&self.query)
+            .unwrap_or_else(|e| println!("Failed to save the query history, {}", e));
+        let (sender, receiver) = oneshot::channel();
+        self.output_receiver = Some(receiver);
+        self.sender
+            .try_send(Communication::RunQuery(
+                self.query.clone(),
+                self.in_open_transaction,
+                sender,
+            ))
+            .unwrap_or_else(|_| println!("Failed to send RunQuery"));
+    }
+    fn ui(&mut self, ui: &mut Ui) {
+        let query_edit = ui.add(
+            egui::TextEdit::multiline(&mut self.query)
+                .code_editor()
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+        let is_run_pressed = query_edit.has_focus()
+            && ui.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::Enter));
+        ui.horizontal(|ui| {
+            if ui.button("Close").clicked() {
+                self.return_status = Some(ExitStatus::Ok);
+            }
+            if ui
+                .add_enabled(
+                    self.output_receiver.is_none(),
+                    egui::Button::new("Run (Ctrl+Enter)"),
+                )
+                .clicked()
+                || is_run_pressed
+            {
+                self.run();
+            }
+            ui.menu_button("History", |ui| {
+                if self.history.queries.is_empty() {
+                    ui.label("Nothing run yet");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for query in self.history.queries.iter().rev() {
+                            /* one line per query, long ones cut short */
+                            let mut label: String =
+                                query.split_whitespace().collect::<Vec<&str>>().join(" ");
+                            if label.chars().count() > 80 {
+                                label = label.chars().take(77).collect::<String>() + "...";
+                            }
+                            if ui.button(label).clicked() {
+                                self.query = query.clone();
+                                ui.close_menu();
+                            }
+                        }
+                    });
+            });
+            ui.checkbox(
+                &mut self.in_open_transaction,
+                "Inside the open import transaction",
+            );
+            if self.output_receiver.is_some() {
+                ui.spinner();
+            }
+        });
+        ui.separator();
+        self.output_ui(ui);
+    }
+    fn output_ui(&mut self, ui: &mut Ui) {
+        let result_set = match &self.output {
+            Some(Ok(ConsoleOutput::Rows(result_set))) => result_set,
+            Some(Ok(ConsoleOutput::RowsAffected(rows))) => {
+                ui.colored_label(
+                    egui::Color32::LIGHT_GREEN,
+                    format!("Success! Rows affected: {}", rows),
+                );
+                return;
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("Error! {}", e));
+                return;
+            }
+            None => return,
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!("{} rows", result_set.rows.len()));
+            /* the grid needs headers to map the cols */
+            if ui
+                .add_enabled(
+                    !result_set.columns.is_empty(),
+                    egui::Button::new("Load into the spreadsheet"),
+                )
+                .clicked()
+            {
+                self.sender
+                    .try_send(Communication::LoadResultSet(result_set.clone()))
+                    .unwrap_or_else(|_| println!("Failed to send LoadResultSet"));
+            }
+        });
+        if result_set.columns.is_empty() {
+            return;
+        }
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            let mut table = TableBuilder::new(ui)
+                .striped(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+            for _ in result_set.columns.iter() {
+                table = table.column(Column::auto().at_most(300.0).clip(true));
+            }
+            table
+                .header(20.0, |mut header| {
+                    for column in result_set.columns.iter() {
+                        header.col(|ui| {
+                            ui.strong(column);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(18.0, result_set.rows.len(), |row_index, mut row| {
+                        for value in result_set.rows[row_index].iter() {
+                            row.col(|ui| match value {
+                                Some(value) => {
+                                    ui.label(value);
+                                }
+                                None => {
+                                    ui.weak("NULL");
+                                }
+                            });
+                        }
+                    });
+                });
+        });
+    }
+}
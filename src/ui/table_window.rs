@@ -1,7 +1,11 @@
+use super::db_transaction_window::{OptionsDBTransactionWindow, TransactionType};
 use super::window_manager::{CSQLWindow, ExitStatus};
 use crate::backend::backend_manager::Communication;
-use crate::backend::csv_handler::ImportedData;
-use crate::backend::database_handler::{TableField, Tables};
+use crate::backend::create_table::identifier;
+use crate::backend::csv_handler::{CsvDialect, ImportedData, LineEnding, QuoteStyle};
+use crate::backend::database_handler::{
+    EntriesFilter, FieldDescription, ScriptKind, ScriptOptions, Tables,
+};
 use egui::{ComboBox, Context, Ui};
 use egui_extras::{Column, TableBuilder};
 use if_chain::if_chain;
@@ -17,6 +21,13 @@ pub struct SpreadSheetWindow {
     return_status: Option<ExitStatus>,
     debug_autoload_file_sent: bool,
     is_table_description_request_sent: bool,
+    upsert_key_fields: Vec<String>,
+    import_where_clause: String,
+    import_limit: usize,
+    is_import_limited: bool,
+    csv_dialect: CsvDialect,
+    script_options: ScriptOptions,
+    script_insert_options: OptionsDBTransactionWindow,
 }
 impl SpreadSheetWindow {
     pub fn default(
@@ -33,6 +44,13 @@ impl SpreadSheetWindow {
             debug_autoload_file_sent: false,
             is_table_description_request_sent: false,
             return_status: None,
+            upsert_key_fields: Vec::new(),
+            import_where_clause: String::new(),
+            import_limit: 1000,
+            is_import_limited: true,
+            csv_dialect: CsvDialect::default(),
+            script_options: ScriptOptions::default(),
+            script_insert_options: OptionsDBTransactionWindow::default(),
         }
     }
 }
@@ -60,6 +78,11 @@ impl SpreadSheetWindow {
         } else {
             is_csv_parsed = false;
         }
+        /* rows going into several tables can only be appended */
+        let has_linked_tables = self
+            .db_table_data_handle
+            .try_lock()
+            .is_ok_and(|db_table_data| db_table_data.import_tables().len() > 1);
         /* Program Menu */
         ui.horizontal(|ui| {
             if ui.button("Log into DB").clicked() {
@@ -78,9 +101,7 @@ impl SpreadSheetWindow {
                     if ui.button("Save").clicked() {
                         self.save_file();
                     }
-                    if ui.button("Save as...").clicked() {
-                        self.save_file();
-                    }
+                    ui.menu_button("Save as...", |ui| self.save_as_options(ui));
 
                     ui.add_space(ui.available_width());
                     ui.separator();
@@ -88,15 +109,66 @@ impl SpreadSheetWindow {
                 uis[1].with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.add_enabled_ui(is_csv_parsed, |ui| {
                         if ui.button("Append to DB").clicked() {
-                            todo!();
+                            let transaction_type = if has_linked_tables {
+                                TransactionType::AppendToTables
+                            } else {
+                                TransactionType::Append
+                            };
+                            self.return_status =
+                                Some(ExitStatus::StartTransactionWindow(transaction_type));
                         }
-                        if ui.button("Save to DB").clicked() {
-                            self.return_status = Some(ExitStatus::StartTransactionWindow);
+                        if ui.button("Test import").clicked() {
+                            self.return_status = Some(ExitStatus::StartTransactionWindow(
+                                TransactionType::TestImport,
+                            ));
+                        }
+                        ui.add_enabled_ui(!has_linked_tables, |ui| {
+                            if ui.button("Save to DB").clicked() {
+                                self.return_status =
+                                    Some(ExitStatus::StartTransactionWindow(TransactionType::Save));
+                            }
+                            ui.menu_button("Upsert to DB", |ui| self.upsert_options(ui));
+                            ui.menu_button("Export SQL", |ui| self.script_options(ui));
+                        });
+                    });
+                    ui.menu_button("Compare with DB", |ui| self.diff_options(ui));
+                    ui.add_enabled_ui(self.current_table.is_some(), |ui| {
+                        if ui.button("Snapshots").clicked() {
+                            self.return_status = Some(ExitStatus::StartSnapshotWindow);
+                        }
+                    });
+                    if ui.button("Past imports").clicked() {
+                        self.return_status = Some(ExitStatus::StartImportJournalWindow);
+                    }
+                    if ui.button("SQL console").clicked() {
+                        self.return_status = Some(ExitStatus::StartSQLConsoleWindow);
+                    }
+                    if ui.button("Create table").clicked() {
+                        self.create_table();
+                    }
+                    ui.add_enabled_ui(self.current_table.is_some(), |ui| {
+                        if ui.button("Add fields").clicked() {
+                            self.add_columns();
                         }
                     });
-                    if ui.button("Import DB").clicked() {
-                        todo!();
+                    if ui.button("Check duplicates").clicked() {
+                        self.sender
+                            .try_send(Communication::CheckUniqueKeys)
+                            .unwrap_or_else(|err| {
+                                println!("failed to send checkuniquekeys, {}", err)
+                            });
                     }
+                    if ui.button("Check references").clicked() {
+                        self.sender
+                            .try_send(Communication::CheckForeignKeys)
+                            .unwrap_or_else(|err| {
+                                println!("failed to send checkforeignkeys, {}", err)
+                            });
+                    }
+                    ui.add_enabled_ui(self.current_table.is_some(), |ui| {
+                        ui.menu_button("Import DB", |ui| self.import_options(ui));
+                        ui.menu_button("Linked tables", |ui| self.linked_tables_options(ui));
+                    });
                     self.table_options(ui);
                     ui.add_space(ui.available_width());
                 });
@@ -119,14 +191,14 @@ impl SpreadSheetWindow {
         if !csv_data.data.is_empty();
         if let Some(curr_db_table) = db_table_data.tables.get_mut(current_table);
             then {
-                if let Some(curr_db_table_fields) = curr_db_table.fields.as_mut() {
+                if curr_db_table.fields.is_some() {
                     ui.group(|ui| {
                         egui::ScrollArea::horizontal().show(ui, |ui| {
                             SpreadSheetWindow::table_builder(
                                 &mut self.sender,
                                 ui,
                                 csv_data,
-                                curr_db_table_fields,
+                                db_table_data,
                             )
                         });
                     });
@@ -146,7 +218,7 @@ impl SpreadSheetWindow {
         sender: &mut Sender<Communication>,
         ui: &mut Ui,
         csv_data: &mut MutexGuard<ImportedData>,
-        curr_db_table_fields: &mut Vec<TableField>,
+        db_table_data: &mut MutexGuard<Tables>,
     ) {
         let table = TableBuilder::new(ui)
             .striped(true)
@@ -163,12 +235,7 @@ impl SpreadSheetWindow {
             .header(20., |mut header| {
                 /* First Col for row mutators */
                 header.col(|ui| {});
-                SpreadSheetWindow::add_header_cols(
-                    &mut header,
-                    csv_data,
-                    curr_db_table_fields,
-                    sender,
-                );
+                SpreadSheetWindow::add_header_cols(&mut header, csv_data, db_table_data, sender);
             })
             .body(|body| {
                 body.rows(
@@ -223,9 +290,218 @@ impl SpreadSheetWindow {
             });
     }
 
+    /* Pick the fields that identify an existing row, then start the upsert */
+    fn upsert_options(&mut self, ui: &mut Ui) {
+        ui.label("Update rows with the same:");
+        self.key_fields_ui(ui);
+        ui.separator();
+        ui.add_enabled_ui(!self.upsert_key_fields.is_empty(), |ui| {
+            if ui.button("Start upsert").clicked() {
+                self.return_status = Some(ExitStatus::StartTransactionWindow(
+                    TransactionType::Upsert(self.upsert_key_fields.clone()),
+                ));
+                ui.close_menu();
+            }
+        });
+    }
+    /* A checkbox per mapped field of the selected table, for picking upsert keys */
+    fn key_fields_ui(&mut self, ui: &mut Ui) {
+        if_chain! {
+            if let Some(current_table) = self.current_table;
+            if let Ok(db_table_data) = self.db_table_data_handle.try_lock();
+            if let Some(table) = db_table_data.tables.get(current_table);
+            if let Some(fields) = &table.fields;
+            then {
+                for field in fields.iter().filter(|field| field.mapped_to_col.is_some()) {
+                    let mut is_key = self.upsert_key_fields.contains(&field.description.field);
+                    if ui.checkbox(&mut is_key, &field.description.field).changed() {
+                        if is_key {
+                            self.upsert_key_fields.push(field.description.field.clone());
+                        } else {
+                            self.upsert_key_fields
+                                .retain(|key_field| key_field != &field.description.field);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    /* Writes the import into a .sql file instead of running it, for someone else to review and run */
+    fn script_options(&mut self, ui: &mut Ui) {
+        let options = &mut self.script_options;
+        ComboBox::from_label("Rows")
+            .selected_text(options.kind.name())
+            .show_ui(ui, |ui| {
+                for kind in ScriptKind::ALL {
+                    ui.selectable_value(&mut options.kind, kind, kind.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Rows per INSERT:");
+            ui.add(egui::DragValue::new(&mut options.rows_per_insert).clamp_range(1..=1000));
+        });
+        ui.checkbox(&mut options.with_transaction, "Wrap in a transaction");
+        if options.kind == ScriptKind::Upsert {
+            ui.separator();
+            ui.label("Update rows with the same:");
+            self.key_fields_ui(ui);
+            ui.label("Needs a unique index on exactly these fields.");
+        }
+        ui.separator();
+        self.script_insert_options.ui(ui);
+        ui.separator();
+        let is_ready =
+            self.script_options.kind != ScriptKind::Upsert || !self.upsert_key_fields.is_empty();
+        if ui
+            .add_enabled(is_ready, egui::Button::new("Choose file..."))
+            .clicked()
+        {
+            ui.close_menu();
+            self.export_script();
+        }
+    }
+    fn export_script(&mut self) {
+        let mut save_name = "import".to_owned();
+        if let Some(table_i) = self.current_table {
+            if let Ok(db_table_data) = self.db_table_data_handle.try_lock() {
+                save_name = db_table_data.tables.get(table_i).unwrap().name.clone();
+            }
+        }
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("SQL scripts", &["sql"])
+            .set_file_name(format!("{}.sql", save_name).as_str())
+            .save_file()
+        {
+            let mut options = self.script_options.clone();
+            options.key_fields = self.upsert_key_fields.clone();
+            options.insert_options = self.script_insert_options.insert_options();
+            self.sender
+                .try_send(Communication::ExportSQLScript(
+                    path.display().to_string(),
+                    options,
+                ))
+                .unwrap_or_else(|err| println!("failed to send exportsqlscript, {}", err));
+        };
+    }
+
+    /* Loads the selected table into the grid, optionally only some of its rows */
+    fn import_options(&mut self, ui: &mut Ui) {
+        ui.label("WHERE");
+        ui.text_edit_singleline(&mut self.import_where_clause);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.is_import_limited, "LIMIT");
+            ui.add_enabled(
+                self.is_import_limited,
+                egui::DragValue::new(&mut self.import_limit).clamp_range(1..=usize::MAX),
+            );
+        });
+        ui.separator();
+        if ui.button("Import").clicked() {
+            let where_clause = self.import_where_clause.trim();
+            let filter = EntriesFilter {
+                where_clause: (!where_clause.is_empty()).then(|| where_clause.to_owned()),
+                limit: self.is_import_limited.then_some(self.import_limit),
+            };
+            self.sender
+                .try_send(Communication::ImportDBEntries(filter))
+                .unwrap_or_else(|err| println!("failed to send importdbentries, {}", err));
+            ui.close_menu();
+        }
+    }
+
+    /* Tables every row gets appended to along with the selected one, their fields show up
+     * in the column pickers. Tables referencing each other get filled in parents first */
+    fn linked_tables_options(&mut self, ui: &mut Ui) {
+        let current_table = match self.current_table {
+            Some(current_table) => current_table,
+            None => return,
+        };
+        let (mut csv_data, mut db_table_data) = match (
+            self.csv_data_handle.try_lock(),
+            self.db_table_data_handle.try_lock(),
+        ) {
+            (Ok(csv_data), Ok(db_table_data)) => (csv_data, db_table_data),
+            _ => return,
+        };
+        let mut toggled_table = None;
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (table_i, table) in db_table_data.tables.iter().enumerate() {
+                    if table_i == current_table {
+                        continue;
+                    }
+                    let mut is_linked = db_table_data.linked_tables.contains(&table_i);
+                    if ui.checkbox(&mut is_linked, &table.name).changed() {
+                        toggled_table = Some((table_i, is_linked));
+                    }
+                }
+            });
+        match toggled_table {
+            Some((table_i, true)) => {
+                db_table_data.linked_tables.push(table_i);
+                if db_table_data.tables[table_i].fields.is_none() {
+                    self.sender
+                        .try_send(Communication::GetTableDescription(table_i))
+                        .unwrap_or_else(|err| {
+                            println!("failed to send gettabledescription, {}", err)
+                        });
+                }
+            }
+            Some((table_i, false)) => {
+                db_table_data
+                    .linked_tables
+                    .retain(|linked| *linked != table_i);
+                /* its mappings would otherwise still describe the cols */
+                let unlinked_cols: Vec<usize> = db_table_data.tables[table_i]
+                    .fields
+                    .iter_mut()
+                    .flatten()
+                    .filter_map(|field| field.mapped_to_col.take())
+                    .collect();
+                for col in unlinked_cols {
+                    SpreadSheetWindow::describe_col(
+                        &self.sender,
+                        &mut csv_data,
+                        &db_table_data,
+                        col,
+                    );
+                }
+            }
+            None => {}
+        }
+    }
+
+    /* Pick the field rows are matched on, then open the diff */
+    fn diff_options(&mut self, ui: &mut Ui) {
+        ui.label("Match rows on:");
+        if_chain! {
+            if let Some(current_table) = self.current_table;
+            if let Ok(db_table_data) = self.db_table_data_handle.try_lock();
+            if let Some(table) = db_table_data.tables.get(current_table);
+            if let Some(fields) = &table.fields;
+            then {
+                for field in fields.iter().filter(|field| field.mapped_to_col.is_some()) {
+                    if ui.button(&field.description.field).clicked() {
+                        self.return_status =
+                            Some(ExitStatus::StartDiffWindow(field.description.field.clone()));
+                        ui.close_menu();
+                    }
+                }
+            }
+        }
+    }
+
     fn table_options(&mut self, ui: &mut Ui) {
         /* Create table select option, only enable if the tables are discovered yet*/
         if let Ok(db_table_data) = &mut self.db_table_data_handle.try_lock() {
+            /* the backend picks the working table itself after creating one */
+            if db_table_data.current_working_table.is_some()
+                && db_table_data.current_working_table != self.current_table
+            {
+                self.current_table = db_table_data.current_working_table;
+                self.upsert_key_fields.clear();
+            }
             ui.add_enabled_ui(db_table_data.tables.get(0).is_some(), |ui| {
                 let mut select_table = ComboBox::from_label("Select Table").width(100.0);
                 if let Some(table_index) = self.current_table {
@@ -258,6 +534,10 @@ impl SpreadSheetWindow {
                         }
                         if let Some(current_working_table_i) = current_working_table_i {
                             db_table_data.current_working_table = Some(current_working_table_i);
+                            db_table_data
+                                .linked_tables
+                                .retain(|linked| *linked != current_working_table_i);
+                            self.upsert_key_fields.clear();
                         }
                     });
                 });
@@ -270,9 +550,10 @@ impl SpreadSheetWindow {
     pub fn add_header_cols(
         header: &mut egui_extras::TableRow,
         csv_data: &mut MutexGuard<ImportedData>,
-        curr_db_table_fields: &mut Vec<TableField>,
+        db_table_data: &mut MutexGuard<Tables>,
         sender: &mut Sender<Communication>,
     ) {
+        let import_tables = db_table_data.import_tables();
         for i in 0..csv_data.data.cols() {
             header.col(|ui| {
                 ui.vertical_centered_justified(|ui| {
@@ -292,36 +573,76 @@ impl SpreadSheetWindow {
                         combo_box = ComboBox::new(i, "");
                     }
 
-                    //if any field is assinged to this combobox, show it's text, else "----"
-                    if let Some(selected_field) = curr_db_table_fields
-                        .iter()
-                        .find(|field| field.mapped_to_col == Some(i))
-                    {
-                        combo_box =
-                            combo_box.selected_text(selected_field.description.field.clone());
-                    } else {
+                    /* every field assigned to this col, linked tables' ones prefixed with the table */
+                    let mut mapped_fields: Vec<String> = Vec::new();
+                    for (position, table_index) in import_tables.iter().enumerate() {
+                        let table = &db_table_data.tables[*table_index];
+                        for field in table
+                            .fields
+                            .iter()
+                            .flatten()
+                            .filter(|field| field.mapped_to_col == Some(i))
+                        {
+                            mapped_fields.push(if position == 0 {
+                                field.description.field.clone()
+                            } else {
+                                format!("{}.{}", table.name, field.description.field)
+                            });
+                        }
+                    }
+                    if mapped_fields.is_empty() {
                         combo_box = combo_box.selected_text("-----");
+                    } else {
+                        combo_box = combo_box.selected_text(mapped_fields.join(", "));
                     }
 
                     /* When a Field gets attached to Col,  */
                     combo_box.show_ui(ui, |ui| {
-                        for field in curr_db_table_fields.iter_mut() {
-                            if ui
-                                .selectable_value(
-                                    &mut field.mapped_to_col,
-                                    Some(i),
-                                    field.description.field.clone(),
-                                )
-                                .clicked()
-                            {
-                                match sender.try_send(Communication::TryParseCol(i)) {
-                                    Ok(_) => {
-                                        for cel in csv_data.data.iter_col_mut(i) {
-                                            cel.curr_field_description =
-                                                Some(field.description.clone());
+                        if ui
+                            .selectable_label(mapped_fields.is_empty(), "-----")
+                            .clicked()
+                        {
+                            for table_index in import_tables.iter() {
+                                for field in db_table_data.tables[*table_index]
+                                    .fields
+                                    .iter_mut()
+                                    .flatten()
+                                    .filter(|field| field.mapped_to_col == Some(i))
+                                {
+                                    field.mapped_to_col = None;
+                                }
+                            }
+                            SpreadSheetWindow::describe_col(sender, csv_data, db_table_data, i);
+                        }
+                        for (position, table_index) in import_tables.iter().enumerate() {
+                            let table = &mut db_table_data.tables[*table_index];
+                            if position > 0 {
+                                ui.separator();
+                                ui.strong(&table.name);
+                            }
+                            for field in table.fields.iter_mut().flatten() {
+                                if ui
+                                    .selectable_value(
+                                        &mut field.mapped_to_col,
+                                        Some(i),
+                                        field.description.field.clone(),
+                                    )
+                                    .on_hover_text(SpreadSheetWindow::field_info(
+                                        &field.description,
+                                    ))
+                                    .clicked()
+                                {
+                                    match sender.try_send(Communication::TryParseCol(i)) {
+                                        Ok(_) => {
+                                            for cel in csv_data.data.iter_col_mut(i) {
+                                                cel.curr_field_description =
+                                                    Some(field.description.clone());
+                                            }
+                                        }
+                                        Err(e) => {
+                                            println!("failed sending parsecol request, {}", e)
                                         }
                                     }
-                                    Err(e) => println!("failed sending parsecol request, {}", e),
                                 }
                             }
                         }
@@ -332,7 +653,79 @@ impl SpreadSheetWindow {
         }
     }
 
+    /* Describes the col's cells as the first field still mapped to it, or leaves them unmapped */
+    fn describe_col(
+        sender: &Sender<Communication>,
+        csv_data: &mut MutexGuard<ImportedData>,
+        db_table_data: &Tables,
+        col: usize,
+    ) {
+        let description = db_table_data
+            .import_tables()
+            .into_iter()
+            .flat_map(|table_index| db_table_data.tables[table_index].fields.iter().flatten())
+            .find(|field| field.mapped_to_col == Some(col))
+            .map(|field| field.description.clone());
+        match description {
+            Some(description) => {
+                for cell in csv_data.data.iter_col_mut(col) {
+                    cell.curr_field_description = Some(description.clone());
+                }
+                sender
+                    .try_send(Communication::TryParseCol(col))
+                    .unwrap_or_else(|e| println!("failed sending parsecol request, {}", e));
+            }
+            None => {
+                for cell in csv_data.data.iter_col_mut(col) {
+                    cell.curr_field_description = None;
+                    cell.is_parsed = None;
+                }
+                csv_data.parsed_cols.retain(|parsed_col| *parsed_col != col);
+                csv_data.is_parsed = false;
+            }
+        }
+    }
+
+    /* Shown when hovering a field, so picking one doesn't need a look at the schema */
+    fn field_info(description: &FieldDescription) -> String {
+        let mut info = vec![format!("{} {}", description.field, description.field_type)];
+        if description.null == "NO" {
+            info.push("NOT NULL".to_owned());
+        }
+        if let Some(charset) = &description.charset {
+            info.push(format!("charset: {}", charset));
+        }
+        if let Some(foreign_key) = &description.foreign_key {
+            info.push(format!(
+                "references {}({})",
+                foreign_key.table, foreign_key.field
+            ));
+        }
+        if !description.indexes.is_empty() {
+            info.push(format!("indexes: {}", description.indexes.join(", ")));
+        }
+        if !description.comment.is_empty() {
+            info.push(description.comment.clone());
+        }
+        info.join("\n")
+    }
+
+    /* Overwrites the opened file, or asks where to save if there's none */
     pub fn save_file(&mut self) {
+        let path = match self.csv_data_handle.try_lock() {
+            Ok(csv_data) => csv_data.path.clone(),
+            Err(_) => return,
+        };
+        if path.is_empty() {
+            self.save_file_as();
+        } else {
+            self.sender
+                .try_send(Communication::SaveCSV(path, self.csv_dialect.clone()))
+                .unwrap_or_else(|err| println!("failed to send savecsv, {}", err));
+        }
+    }
+
+    pub fn save_file_as(&mut self) {
         let mut save_name = "to-csv".to_owned();
         if let Some(table_i) = self.current_table {
             if let Ok(db_table_data) = self.db_table_data_handle.try_lock() {
@@ -340,15 +733,97 @@ impl SpreadSheetWindow {
             }
         }
         if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Spreadsheets", &["csv"])
             .set_file_name(format!("db-{}-converted.csv", save_name).as_str())
             .save_file()
         {
             self.sender
-                .try_send(Communication::SaveCSV(path.display().to_string()))
-                .unwrap_or_else(|err| println!("failed to send loadimportpath, {}", err));
+                .try_send(Communication::SaveCSV(
+                    path.display().to_string(),
+                    self.csv_dialect.clone(),
+                ))
+                .unwrap_or_else(|err| println!("failed to send savecsv, {}", err));
         };
     }
 
+    /* Named after the opened file, the window proposes the fields */
+    fn create_table(&mut self) {
+        let table_name = match self.csv_data_handle.try_lock() {
+            Ok(csv_data) => std::path::Path::new(&csv_data.path)
+                .file_stem()
+                .map(|stem| identifier(&stem.to_string_lossy()))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "imported".to_owned()),
+            Err(_) => return,
+        };
+        self.return_status = Some(ExitStatus::StartCreateTableWindow(table_name));
+    }
+
+    /* Offers the cols no field of the working or linked tables is mapped to */
+    fn add_columns(&mut self) {
+        let (csv_data, db_table_data) = match (
+            self.csv_data_handle.try_lock(),
+            self.db_table_data_handle.try_lock(),
+        ) {
+            (Ok(csv_data), Ok(db_table_data)) => (csv_data, db_table_data),
+            _ => return,
+        };
+        let mapped_cols: Vec<usize> = db_table_data
+            .import_tables()
+            .into_iter()
+            .filter_map(|table_index| db_table_data.tables.get(table_index))
+            .flat_map(|table| table.fields.iter().flatten())
+            .filter_map(|field| field.mapped_to_col)
+            .collect();
+        let unmapped_cols = (0..csv_data.data.cols())
+            .filter(|col_index| !mapped_cols.contains(col_index))
+            .map(|col_index| {
+                let header = if csv_data.are_headers && !csv_data.data.is_empty() {
+                    csv_data.data[0][col_index].data.clone()
+                } else {
+                    format!("Column {}", col_index + 1)
+                };
+                (col_index, header)
+            })
+            .collect();
+        self.return_status = Some(ExitStatus::StartAddColumnsWindow(unmapped_cols));
+    }
+
+    fn save_as_options(&mut self, ui: &mut Ui) {
+        let dialect = &mut self.csv_dialect;
+        let delimiter_name = CsvDialect::DELIMITERS
+            .iter()
+            .find(|(delimiter, _)| *delimiter == dialect.delimiter)
+            .map_or("", |(_, name)| name);
+        ComboBox::from_label("Delimiter")
+            .selected_text(delimiter_name)
+            .show_ui(ui, |ui| {
+                for (delimiter, name) in CsvDialect::DELIMITERS {
+                    ui.selectable_value(&mut dialect.delimiter, delimiter, name);
+                }
+            });
+        ComboBox::from_label("Quoting")
+            .selected_text(dialect.quote_style.name())
+            .show_ui(ui, |ui| {
+                for quote_style in QuoteStyle::ALL {
+                    ui.selectable_value(&mut dialect.quote_style, quote_style, quote_style.name());
+                }
+            });
+        ComboBox::from_label("Line endings")
+            .selected_text(dialect.line_ending.name())
+            .show_ui(ui, |ui| {
+                for line_ending in LineEnding::ALL {
+                    ui.selectable_value(&mut dialect.line_ending, line_ending, line_ending.name());
+                }
+            });
+        ui.checkbox(&mut dialect.with_bom, "UTF-8 BOM (for Excel)");
+        ui.separator();
+        if ui.button("Choose file...").clicked() {
+            ui.close_menu();
+            self.save_file_as();
+        }
+    }
+
     pub fn open_file(&mut self) {
         let final_path;
         /*preloads file in debug */
@@ -1,8 +1,14 @@
-use super::db_transaction_window::DBTransactionWindow;
+use super::db_transaction_window::{DBTransactionWindow, TransactionType};
 use crate::backend::backend_manager::Communication;
 use crate::backend::csv_handler::ImportedData;
 use crate::backend::database_handler::Tables;
+use crate::ui::add_columns_window::AddColumnsWindow;
+use crate::ui::create_table_window::CreateTableWindow;
 use crate::ui::db_login_window::DBLoginWindow;
+use crate::ui::diff_window::DiffWindow;
+use crate::ui::import_journal_window::ImportJournalWindow;
+use crate::ui::snapshot_window::SnapshotWindow;
+use crate::ui::sql_console_window::SQLConsoleWindow;
 use crate::ui::table_window::SpreadSheetWindow;
 use eframe::{run_native, App, NativeOptions};
 use if_chain::*;
@@ -33,10 +39,22 @@ pub fn create_ui(
                 ),
                 db_login_window: None,
                 db_transaction_window: None,
+                diff_window: None,
+                snapshot_window: None,
+                import_journal_window: None,
+                create_table_window: None,
+                add_columns_window: None,
+                sql_console_window: None,
                 sender,
                 csv_data_handle,
                 db_table_data_handle,
-                should_open_transaction_window: false,
+                should_open_transaction_window: None,
+                should_open_diff_window: None,
+                should_open_snapshot_window: false,
+                should_open_import_journal_window: false,
+                should_open_create_table_window: None,
+                should_open_add_columns_window: None,
+                should_open_sql_console_window: false,
                 should_open_login_window: true,
             })
         }),
@@ -47,10 +65,24 @@ struct CSQL {
     spreadsheet_window: SpreadSheetWindow,
     db_login_window: Option<DBLoginWindow>,
     db_transaction_window: Option<DBTransactionWindow>,
+    diff_window: Option<DiffWindow>,
+    snapshot_window: Option<SnapshotWindow>,
+    import_journal_window: Option<ImportJournalWindow>,
+    create_table_window: Option<CreateTableWindow>,
+    add_columns_window: Option<AddColumnsWindow>,
+    sql_console_window: Option<SQLConsoleWindow>,
     sender: Sender<Communication>,
     csv_data_handle: Arc<Mutex<ImportedData>>,
     db_table_data_handle: Arc<Mutex<Tables>>,
-    should_open_transaction_window: bool,
+    should_open_transaction_window: Option<TransactionType>,
+    should_open_diff_window: Option<String>,
+    should_open_snapshot_window: bool,
+    should_open_import_journal_window: bool,
+    /* the table name proposed first */
+    should_open_create_table_window: Option<String>,
+    /* (col, header) of the cols no field is mapped to */
+    should_open_add_columns_window: Option<Vec<(usize, String)>>,
+    should_open_sql_console_window: bool,
     should_open_login_window: bool,
 }
 
@@ -61,10 +93,28 @@ impl App for CSQL {
                 match result {
                     Ok(status) => match status {
                         ExitStatus::StartLoginWindow => self.should_open_login_window = true,
-                        ExitStatus::StartTransactionWindow => {
-                            self.should_open_transaction_window = true;
+                        ExitStatus::StartTransactionWindow(transaction_type) => {
+                            self.should_open_transaction_window = Some(transaction_type);
                             println!("should_open_transaction_window");
                         }
+                        ExitStatus::StartDiffWindow(key_field) => {
+                            self.should_open_diff_window = Some(key_field);
+                        }
+                        ExitStatus::StartSnapshotWindow => {
+                            self.should_open_snapshot_window = true;
+                        }
+                        ExitStatus::StartImportJournalWindow => {
+                            self.should_open_import_journal_window = true;
+                        }
+                        ExitStatus::StartCreateTableWindow(table_name) => {
+                            self.should_open_create_table_window = Some(table_name);
+                        }
+                        ExitStatus::StartAddColumnsWindow(unmapped_cols) => {
+                            self.should_open_add_columns_window = Some(unmapped_cols);
+                        }
+                        ExitStatus::StartSQLConsoleWindow => {
+                            self.should_open_sql_console_window = true;
+                        }
                         _ => {}
                     },
                     _ => (),
@@ -87,22 +137,102 @@ impl App for CSQL {
                 }
             }
 
-            if self.should_open_transaction_window {
+            if let Some(key_field) = &self.should_open_diff_window {
+                if let Some(diff_window) = self.diff_window.as_mut() {
+                    if let Some(Ok(status)) = diff_window.refresh(ctx, ui, frame) {
+                        self.diff_window = None;
+                        self.should_open_diff_window = None;
+                        /* the diff window can hand over straight to an upsert */
+                        if let ExitStatus::StartTransactionWindow(transaction_type) = status {
+                            self.should_open_transaction_window = Some(transaction_type);
+                        }
+                    }
+                } else {
+                    self.diff_window =
+                        Some(DiffWindow::default(self.sender.clone(), key_field.clone()));
+                }
+            }
+
+            if self.should_open_snapshot_window {
+                if let Some(snapshot_window) = self.snapshot_window.as_mut() {
+                    if let Some(Ok(_)) = snapshot_window.refresh(ctx, ui, frame) {
+                        self.snapshot_window = None;
+                        self.should_open_snapshot_window = false;
+                    }
+                } else {
+                    self.snapshot_window = Some(SnapshotWindow::default(self.sender.clone()));
+                }
+            }
+
+            if self.should_open_import_journal_window {
+                if let Some(import_journal_window) = self.import_journal_window.as_mut() {
+                    if let Some(Ok(_)) = import_journal_window.refresh(ctx, ui, frame) {
+                        self.import_journal_window = None;
+                        self.should_open_import_journal_window = false;
+                    }
+                } else {
+                    self.import_journal_window =
+                        Some(ImportJournalWindow::default(self.sender.clone()));
+                }
+            }
+
+            if let Some(table_name) = &self.should_open_create_table_window {
+                if let Some(create_table_window) = self.create_table_window.as_mut() {
+                    if let Some(Ok(_)) = create_table_window.refresh(ctx, ui, frame) {
+                        self.create_table_window = None;
+                        self.should_open_create_table_window = None;
+                    }
+                } else {
+                    self.create_table_window = Some(CreateTableWindow::default(
+                        self.sender.clone(),
+                        table_name.clone(),
+                    ));
+                }
+            }
+
+            if let Some(unmapped_cols) = &self.should_open_add_columns_window {
+                if let Some(add_columns_window) = self.add_columns_window.as_mut() {
+                    if let Some(Ok(_)) = add_columns_window.refresh(ctx, ui, frame) {
+                        self.add_columns_window = None;
+                        self.should_open_add_columns_window = None;
+                    }
+                } else {
+                    self.add_columns_window = Some(AddColumnsWindow::default(
+                        self.sender.clone(),
+                        unmapped_cols.clone(),
+                    ));
+                }
+            }
+
+            if self.should_open_sql_console_window {
+                if let Some(sql_console_window) = self.sql_console_window.as_mut() {
+                    if let Some(Ok(_)) = sql_console_window.refresh(ctx, ui, frame) {
+                        self.sql_console_window = None;
+                        self.should_open_sql_console_window = false;
+                    }
+                } else {
+                    self.sql_console_window = Some(SQLConsoleWindow::default(self.sender.clone()));
+                }
+            }
+
+            if let Some(transaction_type) = &self.should_open_transaction_window {
                 println!("inside if.shoud...");
                 if let Some(db_transaction_window) = self.db_transaction_window.as_mut() {
                     if let Some(result) = db_transaction_window.refresh(ctx, ui, frame) {
                         match result {
                             Ok(status) => {
                                 self.db_transaction_window = None;
-                                self.should_open_transaction_window = false;
+                                self.should_open_transaction_window = None;
                             }
                             _ => (),
                         }
                     }
                 } else {
                     println!("Found a current_working _table");
-                    self.db_transaction_window =
-                        Some(DBTransactionWindow::default(self.sender.clone()))
+                    self.db_transaction_window = Some(DBTransactionWindow::default(
+                        self.sender.clone(),
+                        transaction_type.clone(),
+                    ))
                 }
             }
         });
@@ -117,9 +247,15 @@ pub trait CSQLWindow {
         frame: &mut eframe::Frame,
     ) -> Option<Result<ExitStatus, Box<dyn std::error::Error>>>;
 }
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum ExitStatus {
-    StartTransactionWindow,
+    StartTransactionWindow(TransactionType),
     StartLoginWindow,
+    StartDiffWindow(String),
+    StartSnapshotWindow,
+    StartImportJournalWindow,
+    StartCreateTableWindow(String),
+    StartAddColumnsWindow(Vec<(usize, String)>),
+    StartSQLConsoleWindow,
     Ok,
 }